@@ -15,6 +15,73 @@
 /*!40101 SET @OLD_SQL_MODE=@@SQL_MODE, SQL_MODE='NO_AUTO_VALUE_ON_ZERO' */;
 /*!40111 SET @OLD_SQL_NOTES=@@SQL_NOTES, SQL_NOTES=0 */;
 
+--
+-- Table structure for table `api_keys`
+--
+
+DROP TABLE IF EXISTS `api_keys`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `api_keys` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int NOT NULL,
+  `name` varchar(100) NOT NULL,
+  `prefix` varchar(16) NOT NULL,
+  `key_hash` char(64) NOT NULL,
+  `scopes` varchar(255) NOT NULL,
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  `last_used_at` datetime DEFAULT NULL,
+  `expires_at` datetime DEFAULT NULL,
+  `revoked_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `key_hash` (`key_hash`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `api_keys_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `api_keys`
+--
+
+LOCK TABLES `api_keys` WRITE;
+/*!40000 ALTER TABLE `api_keys` DISABLE KEYS */;
+/*!40000 ALTER TABLE `api_keys` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `application_status_history`
+--
+
+DROP TABLE IF EXISTS `application_status_history`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `application_status_history` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `application_id` int NOT NULL,
+  `from_status` varchar(20) DEFAULT NULL,
+  `to_status` varchar(20) NOT NULL,
+  `changed_by` int DEFAULT NULL,
+  `note` text,
+  `changed_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  KEY `application_id` (`application_id`),
+  KEY `changed_by` (`changed_by`),
+  CONSTRAINT `application_status_history_ibfk_1` FOREIGN KEY (`application_id`) REFERENCES `applications` (`id`),
+  CONSTRAINT `application_status_history_ibfk_2` FOREIGN KEY (`changed_by`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `application_status_history`
+--
+
+LOCK TABLES `application_status_history` WRITE;
+/*!40000 ALTER TABLE `application_status_history` DISABLE KEYS */;
+INSERT INTO `application_status_history` VALUES (1,9,NULL,'applied',2,NULL,'2025-06-26 05:49:20'),(2,10,NULL,'applied',2,NULL,'2025-06-27 03:24:39');
+/*!40000 ALTER TABLE `application_status_history` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `applications`
 --
@@ -31,6 +98,10 @@ CREATE TABLE `applications` (
   `applied_at` datetime DEFAULT CURRENT_TIMESTAMP,
   `resume_link` text,
   `cover_letter` text,
+  `status` varchar(20) NOT NULL DEFAULT 'applied',
+  `status_updated_at` datetime DEFAULT NULL,
+  `withdrawn_at` datetime DEFAULT NULL,
+  `withdrawal_reason` text,
   PRIMARY KEY (`id`),
   UNIQUE KEY `unique_application` (`user_id`,`job_id`),
   KEY `job_id` (`job_id`),
@@ -45,7 +116,7 @@ CREATE TABLE `applications` (
 
 LOCK TABLES `applications` WRITE;
 /*!40000 ALTER TABLE `applications` DISABLE KEYS */;
-INSERT INTO `applications` VALUES (9,2,10,'John Doe','john@example.com','2025-06-26 05:49:20','https://example.com/resume.pdf','I am excited to contribute to your company.'),(10,2,1,'Jane Doe','jane@example.com','2025-06-27 03:24:39','https://example.com/resume.pdf','I am excited to apply for this job.');
+INSERT INTO `applications` VALUES (9,2,10,'John Doe','john@example.com','2025-06-26 05:49:20','https://example.com/resume.pdf','I am excited to contribute to your company.','applied',NULL,NULL,NULL),(10,2,1,'Jane Doe','jane@example.com','2025-06-27 03:24:39','https://example.com/resume.pdf','I am excited to apply for this job.','applied',NULL,NULL,NULL);
 /*!40000 ALTER TABLE `applications` ENABLE KEYS */;
 UNLOCK TABLES;
 
@@ -64,6 +135,16 @@ CREATE TABLE `companies` (
   `created_at` datetime DEFAULT NULL,
   `user_id` int DEFAULT NULL,
   `website` varchar(255) DEFAULT NULL,
+  `deleted_at` datetime DEFAULT NULL,
+  `industry` varchar(100) DEFAULT NULL,
+  `size_range` varchar(20) DEFAULT NULL,
+  `founded_year` smallint DEFAULT NULL,
+  `linkedin_url` varchar(255) DEFAULT NULL,
+  `twitter_url` varchar(255) DEFAULT NULL,
+  `facebook_url` varchar(255) DEFAULT NULL,
+  `logo_url` varchar(512) DEFAULT NULL,
+  `updated_at` datetime DEFAULT NULL,
+  `cover_image_url` varchar(512) DEFAULT NULL,
   PRIMARY KEY (`id`)
 ) ENGINE=InnoDB AUTO_INCREMENT=4 DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
 /*!40101 SET character_set_client = @saved_cs_client */;
@@ -74,10 +155,134 @@ CREATE TABLE `companies` (
 
 LOCK TABLES `companies` WRITE;
 /*!40000 ALTER TABLE `companies` DISABLE KEYS */;
-INSERT INTO `companies` VALUES (1,'OpenAI Nigeria','Lagos','AI company focused on Rust backend roles','2025-06-17 17:58:41',2,NULL),(2,'Google Developer Groups Nigeria','Lagos','Focused on developers interest on building with AI tools','2025-06-17 18:38:02',2,NULL),(3,'OpenAI Nigeria','Abuja','AI Research & Tools','2025-06-19 12:19:34',NULL,'https://openai.com');
+INSERT INTO `companies` VALUES (1,'OpenAI Nigeria','Lagos','AI company focused on Rust backend roles','2025-06-17 17:58:41',2,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL),(2,'Google Developer Groups Nigeria','Lagos','Focused on developers interest on building with AI tools','2025-06-17 18:38:02',2,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL),(3,'OpenAI Nigeria','Abuja','AI Research & Tools','2025-06-19 12:19:34',NULL,'https://openai.com',NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL,NULL);
 /*!40000 ALTER TABLE `companies` ENABLE KEYS */;
 UNLOCK TABLES;
 
+--
+-- Table structure for table `company_media`
+--
+
+DROP TABLE IF EXISTS `company_media`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `company_media` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `company_id` int NOT NULL,
+  `kind` varchar(20) NOT NULL,
+  `variant` varchar(20) NOT NULL,
+  `storage_key` varchar(512) NOT NULL,
+  `content_type` varchar(50) NOT NULL,
+  `width` int NOT NULL,
+  `height` int NOT NULL,
+  `byte_size` int NOT NULL,
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `company_kind_variant` (`company_id`,`kind`,`variant`),
+  CONSTRAINT `company_media_ibfk_1` FOREIGN KEY (`company_id`) REFERENCES `companies` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `company_media`
+--
+
+LOCK TABLES `company_media` WRITE;
+/*!40000 ALTER TABLE `company_media` DISABLE KEYS */;
+/*!40000 ALTER TABLE `company_media` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `company_members`
+--
+
+DROP TABLE IF EXISTS `company_members`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `company_members` (
+  `company_id` int NOT NULL,
+  `user_id` int NOT NULL,
+  `role` varchar(20) NOT NULL DEFAULT 'viewer',
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`company_id`,`user_id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `company_members_ibfk_1` FOREIGN KEY (`company_id`) REFERENCES `companies` (`id`),
+  CONSTRAINT `company_members_ibfk_2` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `company_members`
+--
+
+LOCK TABLES `company_members` WRITE;
+/*!40000 ALTER TABLE `company_members` DISABLE KEYS */;
+INSERT INTO `company_members` VALUES (1,2,'owner','2025-06-17 17:58:41'),(2,2,'owner','2025-06-17 18:38:02');
+/*!40000 ALTER TABLE `company_members` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `impersonation_requests`
+--
+
+DROP TABLE IF EXISTS `impersonation_requests`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `impersonation_requests` (
+  `id` bigint NOT NULL AUTO_INCREMENT,
+  `impersonation_id` int NOT NULL,
+  `method` varchar(10) NOT NULL,
+  `path` varchar(2048) NOT NULL,
+  `status` smallint NOT NULL,
+  `requested_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  KEY `impersonation_id` (`impersonation_id`),
+  CONSTRAINT `impersonation_requests_ibfk_1` FOREIGN KEY (`impersonation_id`) REFERENCES `impersonations` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `impersonation_requests`
+--
+
+LOCK TABLES `impersonation_requests` WRITE;
+/*!40000 ALTER TABLE `impersonation_requests` DISABLE KEYS */;
+/*!40000 ALTER TABLE `impersonation_requests` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `impersonations`
+--
+
+DROP TABLE IF EXISTS `impersonations`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `impersonations` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `admin_id` int NOT NULL,
+  `target_user_id` int NOT NULL,
+  `reason` varchar(255) NOT NULL,
+  `read_only` tinyint(1) NOT NULL DEFAULT '1',
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  `expires_at` datetime NOT NULL,
+  `ended_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `admin_id` (`admin_id`),
+  KEY `target_user_id` (`target_user_id`),
+  CONSTRAINT `impersonations_ibfk_1` FOREIGN KEY (`admin_id`) REFERENCES `users` (`id`),
+  CONSTRAINT `impersonations_ibfk_2` FOREIGN KEY (`target_user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `impersonations`
+--
+
+LOCK TABLES `impersonations` WRITE;
+/*!40000 ALTER TABLE `impersonations` DISABLE KEYS */;
+/*!40000 ALTER TABLE `impersonations` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `jobs`
 --
@@ -96,6 +301,10 @@ CREATE TABLE `jobs` (
   `job_type` varchar(50) DEFAULT 'full-time',
   `tags` text,
   `views` int DEFAULT '0',
+  `updated_at` datetime DEFAULT NULL,
+  `status` varchar(20) NOT NULL DEFAULT 'draft',
+  `expires_at` datetime DEFAULT NULL,
+  `deleted_at` datetime DEFAULT NULL,
   PRIMARY KEY (`id`),
   KEY `company_id` (`company_id`),
   KEY `user_id` (`user_id`),
@@ -110,10 +319,225 @@ CREATE TABLE `jobs` (
 
 LOCK TABLES `jobs` WRITE;
 /*!40000 ALTER TABLE `jobs` DISABLE KEYS */;
-INSERT INTO `jobs` VALUES (1,'Rust Backend Engineer','Work on high-performance backend systems using Rust and Actix.',NULL,1,NULL,'2025-06-17 18:38:40','full-time',NULL,2),(10,'Backend Developer','Build and maintain backend APIs using Rust','Remote',1,2,'2025-06-19 12:15:57','Full-time','rust, api, backend',0);
+INSERT INTO `jobs` VALUES (1,'Rust Backend Engineer','Work on high-performance backend systems using Rust and Actix.',NULL,1,NULL,'2025-06-17 18:38:40','full-time',NULL,2,NULL,'published',NULL,NULL),(10,'Backend Developer','Build and maintain backend APIs using Rust','Remote',1,2,'2025-06-19 12:15:57','Full-time','rust, api, backend',0,NULL,'published',NULL,NULL);
 /*!40000 ALTER TABLE `jobs` ENABLE KEYS */;
 UNLOCK TABLES;
 
+--
+-- Table structure for table `login_attempts`
+--
+
+DROP TABLE IF EXISTS `login_attempts`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `login_attempts` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int DEFAULT NULL,
+  `email` varchar(255) NOT NULL,
+  `ip_address` varchar(45) DEFAULT NULL,
+  `succeeded` tinyint(1) NOT NULL DEFAULT '0',
+  `attempted_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  KEY `ip_attempted` (`ip_address`,`attempted_at`),
+  CONSTRAINT `login_attempts_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `login_attempts`
+--
+
+LOCK TABLES `login_attempts` WRITE;
+/*!40000 ALTER TABLE `login_attempts` DISABLE KEYS */;
+/*!40000 ALTER TABLE `login_attempts` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `mfa_recovery_codes`
+--
+
+DROP TABLE IF EXISTS `mfa_recovery_codes`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `mfa_recovery_codes` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int NOT NULL,
+  `code_hash` char(64) NOT NULL,
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  `used_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `user_code` (`user_id`,`code_hash`),
+  CONSTRAINT `mfa_recovery_codes_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `mfa_recovery_codes`
+--
+
+LOCK TABLES `mfa_recovery_codes` WRITE;
+/*!40000 ALTER TABLE `mfa_recovery_codes` DISABLE KEYS */;
+/*!40000 ALTER TABLE `mfa_recovery_codes` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `oidc_login_states`
+--
+
+DROP TABLE IF EXISTS `oidc_login_states`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `oidc_login_states` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `state_hash` char(64) NOT NULL,
+  `code_verifier` varchar(128) NOT NULL,
+  `nonce` varchar(128) NOT NULL,
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  `expires_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `state_hash` (`state_hash`),
+  KEY `expires_at` (`expires_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `oidc_login_states`
+--
+
+LOCK TABLES `oidc_login_states` WRITE;
+/*!40000 ALTER TABLE `oidc_login_states` DISABLE KEYS */;
+/*!40000 ALTER TABLE `oidc_login_states` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `refresh_tokens`
+--
+
+DROP TABLE IF EXISTS `refresh_tokens`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `refresh_tokens` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int NOT NULL,
+  `family_id` char(64) NOT NULL,
+  `token_hash` char(64) NOT NULL,
+  `expires_at` datetime NOT NULL,
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  `used_at` datetime DEFAULT NULL,
+  `revoked_at` datetime DEFAULT NULL,
+  `mfa` tinyint(1) NOT NULL DEFAULT '0',
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `token_hash` (`token_hash`),
+  KEY `family_id` (`family_id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `refresh_tokens_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `refresh_tokens`
+--
+
+LOCK TABLES `refresh_tokens` WRITE;
+/*!40000 ALTER TABLE `refresh_tokens` DISABLE KEYS */;
+/*!40000 ALTER TABLE `refresh_tokens` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `sessions`
+--
+
+DROP TABLE IF EXISTS `sessions`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `sessions` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int NOT NULL,
+  `family_id` char(64) NOT NULL,
+  `user_agent` varchar(255) DEFAULT NULL,
+  `ip_address` varchar(45) DEFAULT NULL,
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  `last_seen_at` datetime DEFAULT NULL,
+  `revoked_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `family_id` (`family_id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `sessions_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `sessions`
+--
+
+LOCK TABLES `sessions` WRITE;
+/*!40000 ALTER TABLE `sessions` DISABLE KEYS */;
+/*!40000 ALTER TABLE `sessions` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `user_identities`
+--
+
+DROP TABLE IF EXISTS `user_identities`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `user_identities` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int NOT NULL,
+  `provider` varchar(64) NOT NULL,
+  `subject` varchar(255) NOT NULL,
+  `email` varchar(255) DEFAULT NULL,
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  `last_login_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `provider_subject` (`provider`,`subject`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `user_identities_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `user_identities`
+--
+
+LOCK TABLES `user_identities` WRITE;
+/*!40000 ALTER TABLE `user_identities` DISABLE KEYS */;
+/*!40000 ALTER TABLE `user_identities` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `user_tokens`
+--
+
+DROP TABLE IF EXISTS `user_tokens`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `user_tokens` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int NOT NULL,
+  `purpose` varchar(32) NOT NULL,
+  `token_hash` char(64) NOT NULL,
+  `expires_at` datetime NOT NULL,
+  `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
+  `used_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `token_hash` (`token_hash`),
+  KEY `user_purpose` (`user_id`,`purpose`),
+  CONSTRAINT `user_tokens_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `user_tokens`
+--
+
+LOCK TABLES `user_tokens` WRITE;
+/*!40000 ALTER TABLE `user_tokens` DISABLE KEYS */;
+/*!40000 ALTER TABLE `user_tokens` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `users`
 --
@@ -125,9 +549,16 @@ CREATE TABLE `users` (
   `id` int NOT NULL AUTO_INCREMENT,
   `email` varchar(255) NOT NULL,
   `password_hash` text NOT NULL,
-  `role` varchar(50) NOT NULL DEFAULT 'user',
+  `role` varchar(50) NOT NULL DEFAULT 'job_seeker',
   `created_at` datetime DEFAULT CURRENT_TIMESTAMP,
   `updated_at` datetime DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  `deleted_at` datetime DEFAULT NULL,
+  `email_verified_at` datetime DEFAULT NULL,
+  `failed_login_count` int NOT NULL DEFAULT '0',
+  `locked_until` datetime DEFAULT NULL,
+  `totp_secret` varchar(64) DEFAULT NULL,
+  `totp_enabled_at` datetime DEFAULT NULL,
+  `totp_last_step` bigint DEFAULT NULL,
   PRIMARY KEY (`id`),
   UNIQUE KEY `email` (`email`)
 ) ENGINE=InnoDB AUTO_INCREMENT=5 DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
@@ -139,7 +570,7 @@ CREATE TABLE `users` (
 
 LOCK TABLES `users` WRITE;
 /*!40000 ALTER TABLE `users` DISABLE KEYS */;
-INSERT INTO `users` VALUES (2,'chinemeremezenagu@gmail.com','$argon2i$v=19$m=16,t=2,p=1$Q29kZVdpdGhFemU$mFhC8G/5VulKDyKF7XOs/g','admin','2025-06-17 02:47:06','2025-06-17 02:47:06'),(3,'classicman695@gmail.com','$argon2id$v=19$m=19456,t=2,p=1$jGHAITCDfzzZ8JvpXQUyJA$gpGWbgXTRkhFrqUt3HQN21uRXwsd0w9v1eknbk+/nfw','user','2025-06-17 06:59:18','2025-06-17 06:59:18'),(4,'ezenaguchinemerempaschal@gmail.com','$argon2id$v=19$m=19456,t=2,p=1$kIb4lMylxdyCIuqPVeHj8A$8RJ5hoEhS7MPpEUmdcz5GLLk5dRB0SV3ksL7kRlkbFA','user','2025-06-19 09:39:42','2025-06-19 09:39:42');
+INSERT INTO `users` VALUES (2,'chinemeremezenagu@gmail.com','$argon2i$v=19$m=16,t=2,p=1$Q29kZVdpdGhFemU$mFhC8G/5VulKDyKF7XOs/g','admin','2025-06-17 02:47:06','2025-06-17 02:47:06',NULL,'2025-06-17 02:47:06',0,NULL,NULL,NULL,NULL),(3,'classicman695@gmail.com','$argon2id$v=19$m=19456,t=2,p=1$jGHAITCDfzzZ8JvpXQUyJA$gpGWbgXTRkhFrqUt3HQN21uRXwsd0w9v1eknbk+/nfw','job_seeker','2025-06-17 06:59:18','2025-06-17 06:59:18',NULL,'2025-06-17 06:59:18',0,NULL,NULL,NULL,NULL),(4,'ezenaguchinemerempaschal@gmail.com','$argon2id$v=19$m=19456,t=2,p=1$kIb4lMylxdyCIuqPVeHj8A$8RJ5hoEhS7MPpEUmdcz5GLLk5dRB0SV3ksL7kRlkbFA','job_seeker','2025-06-19 09:39:42','2025-06-19 09:39:42',NULL,'2025-06-19 09:39:42',0,NULL,NULL,NULL,NULL);
 /*!40000 ALTER TABLE `users` ENABLE KEYS */;
 UNLOCK TABLES;
 /*!40103 SET TIME_ZONE=@OLD_TIME_ZONE */;
@@ -0,0 +1,164 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde_json::{json, Value};
+use sqlx::mysql::MySqlDatabaseError;
+use std::fmt;
+
+/// MySQL: cannot add or update a child row, its parent does not exist
+const ER_NO_REFERENCED_ROW: u16 = 1452;
+
+/// Crate-wide error type. Every failure leaves the API as
+/// `{ "code": ..., "message": ..., "details": ... }` JSON with a matching status.
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    TooManyRequests(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    /// Request was well-formed but failed validation; `details` says why
+    Validation { message: String, details: Value },
+    /// An upstream service (e.g. the OIDC provider) failed or misbehaved
+    BadGateway(String),
+    Internal,
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::BadRequest(message.into())
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::Conflict(message.into())
+    }
+
+    pub fn too_many_requests(message: impl Into<String>) -> Self {
+        ApiError::TooManyRequests(message.into())
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        ApiError::PayloadTooLarge(message.into())
+    }
+
+    pub fn unsupported_media_type(message: impl Into<String>) -> Self {
+        ApiError::UnsupportedMediaType(message.into())
+    }
+
+    pub fn bad_gateway(message: impl Into<String>) -> Self {
+        ApiError::BadGateway(message.into())
+    }
+
+    pub fn validation(message: impl Into<String>, details: Value) -> Self {
+        ApiError::Validation { message: message.into(), details }
+    }
+
+    /// Stable machine-readable code clients can switch on
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::TooManyRequests(_) => "too_many_requests",
+            ApiError::PayloadTooLarge(_) => "payload_too_large",
+            ApiError::UnsupportedMediaType(_) => "unsupported_media_type",
+            ApiError::Validation { .. } => "validation_failed",
+            ApiError::BadGateway(_) => "bad_gateway",
+            ApiError::Internal => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(msg)
+            | ApiError::Unauthorized(msg)
+            | ApiError::Forbidden(msg)
+            | ApiError::NotFound(msg)
+            | ApiError::Conflict(msg)
+            | ApiError::TooManyRequests(msg)
+            | ApiError::PayloadTooLarge(msg)
+            | ApiError::UnsupportedMediaType(msg)
+            | ApiError::BadGateway(msg)
+            | ApiError::Validation { message: msg, .. } => f.write_str(msg),
+            ApiError::Internal => f.write_str("Internal server error"),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let details = match self {
+            ApiError::Validation { details, .. } => details.clone(),
+            _ => Value::Null,
+        };
+
+        HttpResponse::build(self.status_code()).json(json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "details": details,
+        }))
+    }
+}
+
+/// Database errors never reach the client verbatim: missing rows become 404,
+/// constraint violations 409/400, anything else is logged and reported as 500.
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::RowNotFound = err {
+            return ApiError::not_found("Resource not found");
+        }
+
+        if let Some(db_err) = err.as_database_error() {
+            if db_err.is_unique_violation() {
+                return ApiError::conflict("Resource already exists");
+            }
+
+            if db_err.is_foreign_key_violation() {
+                let number = db_err
+                    .try_downcast_ref::<MySqlDatabaseError>()
+                    .map(|e| e.number());
+                return match number {
+                    Some(ER_NO_REFERENCED_ROW) => ApiError::bad_request("Referenced resource does not exist"),
+                    // 1451: deleting a parent row that is still referenced
+                    _ => ApiError::conflict("Resource is still referenced by other records"),
+                };
+            }
+        }
+
+        eprintln!("Database error: {:?}", err);
+        ApiError::Internal
+    }
+}
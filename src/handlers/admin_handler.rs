@@ -1,45 +1,40 @@
-use actix_web::{get, web, HttpResponse, HttpRequest};
+use actix_web::{get, post, delete, web, HttpResponse};
 use sqlx::MySqlPool;
-use serde::Serialize;
-use crate::middleware::auth_middleware::Claims;
-use crate::models::admin::{DashboardStats, RecentJob, RecentApplication, AdminDashboardResponse};
-use actix_web::HttpMessage;
+use crate::errors::ApiError;
+use crate::middleware::extractors::{RequireRole, Admin};
+use crate::models::admin::{
+    DashboardStats, RecentJob, RecentApplication, AdminDashboardResponse, TrashEntity, ImpersonateRequest,
+    ImpersonationResponse,
+};
+use crate::handlers::auth_handler::sign_impersonation_token;
+use crate::middleware::auth_middleware::Actor;
+use crate::models::user::UserRole;
+use crate::utils::jwt::JwtKeys;
+use crate::utils::storage::MediaStorage;
+use chrono::{Duration, NaiveDateTime, Utc};
+use std::env;
 
 
 #[get("/admin/dashboard")]
 pub async fn get_dashboard_stats(
-    req: HttpRequest,
+    _admin: RequireRole<Admin>,
     pool: web::Data<MySqlPool>,
-) -> HttpResponse {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return HttpResponse::Unauthorized().body("Missing auth claims"),
-    };
-
-    if claims.role != "admin" {
-        return HttpResponse::Forbidden().body("Access denied");
-    }
-
-    let total_users = sqlx::query_scalar!("SELECT COUNT(*) FROM users")
+) -> Result<HttpResponse, ApiError> {
+    let total_users = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL")
         .fetch_one(pool.get_ref())
-        .await
-        .unwrap_or(0);
+        .await?;
 
-    let total_jobs = sqlx::query_scalar!("SELECT COUNT(*) FROM jobs")
+    let total_jobs = sqlx::query_scalar!("SELECT COUNT(*) FROM jobs WHERE deleted_at IS NULL")
         .fetch_one(pool.get_ref())
-        .await
-        .unwrap_or(0);
+        .await?;
 
-    let total_companies = sqlx::query_scalar!("SELECT COUNT(*) FROM companies")
+    let total_companies = sqlx::query_scalar!("SELECT COUNT(*) FROM companies WHERE deleted_at IS NULL")
         .fetch_one(pool.get_ref())
-        .await
-        .unwrap_or(0);
+        .await?;
 
     let total_applications = sqlx::query_scalar!("SELECT COUNT(*) FROM applications")
         .fetch_one(pool.get_ref())
-        .await
-        .unwrap_or(0);
+        .await?;
 
     // let recent_jobs = sqlx::query_as!(
     //     RecentJob,
@@ -58,13 +53,13 @@ pub async fn get_dashboard_stats(
         r#"
         SELECT id, title, created_at
         FROM jobs
+        WHERE deleted_at IS NULL
         ORDER BY created_at DESC
         LIMIT 5
         "#
     )
     .fetch_all(pool.get_ref())
-    .await
-    .unwrap_or_default();
+    .await?;
 
     let recent_jobs: Vec<RecentJob> = raw_jobs.into_iter().map(|job| {
         let created_at_pretty = job
@@ -85,13 +80,13 @@ pub async fn get_dashboard_stats(
         FROM applications a
         JOIN jobs j ON a.job_id = j.id
         JOIN users u ON a.user_id = u.id
+        WHERE j.deleted_at IS NULL AND u.deleted_at IS NULL
         ORDER BY a.applied_at DESC
         LIMIT 5
         "#
     )
     .fetch_all(pool.get_ref())
-    .await
-    .unwrap_or_default();
+    .await?;
 
     let recent_applications: Vec<RecentApplication> = raw_applications.into_iter().map(|app| {
         let applied_at = app
@@ -120,5 +115,316 @@ pub async fn get_dashboard_stats(
         recent_applications,
     };
 
-    HttpResponse::Ok().json(response)
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Soft-delete a company (admin only); its jobs disappear from public listings
+#[delete("/admin/companies/{id}")]
+pub async fn soft_delete_company(
+    _admin: RequireRole<Admin>,
+    pool: web::Data<MySqlPool>,
+    company_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let result = sqlx::query!(
+        "UPDATE companies SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+        Utc::now().naive_utc(),
+        *company_id
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Company not found"));
+    }
+
+    Ok(HttpResponse::Ok().body("Company deleted successfully"))
+}
+
+/// Clear a user's failed-login lockout
+#[post("/admin/users/{id}/unlock")]
+pub async fn unlock_user(
+    _admin: RequireRole<Admin>,
+    pool: web::Data<MySqlPool>,
+    user_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let result = sqlx::query!(
+        "UPDATE users SET failed_login_count = 0, locked_until = NULL WHERE id = ? AND deleted_at IS NULL",
+        *user_id
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("User not found"));
+    }
+
+    Ok(HttpResponse::Ok().body("User unlocked"))
+}
+
+/// Lifetime of impersonation tokens (IMPERSONATION_TTL_MINUTES, default 15)
+fn impersonation_ttl() -> Duration {
+    let minutes = env::var("IMPERSONATION_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15);
+    Duration::minutes(minutes)
+}
+
+/// Act as a user to see what they see. Issues a short-lived token carrying the
+/// admin as `act`; it is read-only unless `allow_writes` is set, and every
+/// request made with it is logged.
+#[post("/admin/users/{id}/impersonate")]
+pub async fn impersonate_user(
+    admin: RequireRole<Admin>,
+    pool: web::Data<MySqlPool>,
+    keys: web::Data<JwtKeys>,
+    user_id: web::Path<i32>,
+    info: web::Json<ImpersonateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = *user_id;
+    let reason = info.reason.trim();
+    if reason.is_empty() || reason.chars().count() > 255 {
+        return Err(ApiError::validation(
+            "Invalid impersonation request",
+            serde_json::json!({ "reason": ["must be between 1 and 255 characters"] }),
+        ));
+    }
+
+    let role = sqlx::query_scalar!("SELECT role FROM users WHERE id = ? AND deleted_at IS NULL", user_id)
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::not_found("User not found"))?
+        .parse()
+        .unwrap_or(UserRole::JobSeeker);
+
+    // ⛔ Admin powers never travel through an impersonation token
+    if role == UserRole::Admin || user_id == admin.sub {
+        return Err(ApiError::forbidden("Admins cannot be impersonated"));
+    }
+
+    let now = Utc::now().naive_utc();
+    let ttl = impersonation_ttl();
+    let read_only = !info.allow_writes;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO impersonations (admin_id, target_user_id, reason, read_only, created_at, expires_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        admin.sub,
+        user_id,
+        reason,
+        read_only,
+        now,
+        now + ttl
+    )
+    .execute(pool.get_ref())
+    .await?;
+    let impersonation_id = result.last_insert_id() as i32;
+
+    let actor = Actor { sub: admin.sub, impersonation_id, read_only };
+    let token = sign_impersonation_token(&keys, user_id, role, actor, ttl)?;
+
+    Ok(HttpResponse::Created().json(ImpersonationResponse {
+        impersonation_id,
+        token,
+        token_type: "Bearer".to_string(),
+        expires_in: ttl.num_seconds(),
+        read_only,
+    }))
+}
+
+/// End an impersonation early; its token stops working at once
+#[post("/admin/impersonations/{id}/end")]
+pub async fn end_impersonation(
+    _admin: RequireRole<Admin>,
+    pool: web::Data<MySqlPool>,
+    impersonation_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let result = sqlx::query!(
+        "UPDATE impersonations SET ended_at = ? WHERE id = ? AND ended_at IS NULL",
+        Utc::now().naive_utc(),
+        *impersonation_id
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Impersonation not found or already ended"));
+    }
+
+    Ok(HttpResponse::Ok().body("Impersonation ended"))
+}
+
+/// Restore a soft-deleted job, company or user
+#[post("/admin/{entity}/{id}/restore")]
+pub async fn restore_deleted(
+    _admin: RequireRole<Admin>,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(TrashEntity, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (entity, id) = path.into_inner();
+
+    let sql = format!(
+        "UPDATE {} SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+        entity.table()
+    );
+
+    let result = sqlx::query(&sql).bind(id).execute(pool.get_ref()).await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Nothing to restore"));
+    }
+
+    Ok(HttpResponse::Ok().body("Restored successfully"))
+}
+
+/// Permanently remove a soft-deleted job, company or user together with the
+/// rows that reference it, in a single transaction. A company's uploaded media
+/// is removed from storage once the rows are gone.
+#[delete("/admin/{entity}/{id}/purge")]
+pub async fn purge_deleted(
+    _admin: RequireRole<Admin>,
+    pool: web::Data<MySqlPool>,
+    storage: web::Data<dyn MediaStorage>,
+    path: web::Path<(TrashEntity, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (entity, id) = path.into_inner();
+
+    let sql = format!("SELECT deleted_at FROM {} WHERE id = ?", entity.table());
+    match sqlx::query_scalar::<_, Option<NaiveDateTime>>(&sql)
+        .bind(id)
+        .fetch_optional(pool.get_ref())
+        .await?
+    {
+        None => return Err(ApiError::not_found("Not found")),
+        Some(None) => return Err(ApiError::conflict("Only soft-deleted records can be purged")),
+        Some(Some(_)) => {}
+    }
+
+    let orphaned_media = purge(pool.get_ref(), entity, id).await?;
+    for key in orphaned_media {
+        if let Err(e) = storage.delete(&key).await {
+            eprintln!("Error deleting purged media {}: {}", key, e);
+        }
+    }
+    Ok(HttpResponse::Ok().body("Purged successfully"))
+}
+
+/// Returns the storage keys of media that belonged to the purged rows
+async fn purge(pool: &MySqlPool, entity: TrashEntity, id: i32) -> Result<Vec<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut orphaned_media = vec![];
+
+    match entity {
+        TrashEntity::Jobs => {
+            sqlx::query!(
+                "DELETE h FROM application_status_history h JOIN applications a ON h.application_id = a.id WHERE a.job_id = ?",
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!("DELETE FROM applications WHERE job_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM jobs WHERE id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        TrashEntity::Companies => {
+            sqlx::query!(
+                r#"
+                DELETE h FROM application_status_history h
+                JOIN applications a ON h.application_id = a.id
+                JOIN jobs j ON a.job_id = j.id
+                WHERE j.company_id = ?
+                "#,
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                "DELETE a FROM applications a JOIN jobs j ON a.job_id = j.id WHERE j.company_id = ?",
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!("DELETE FROM jobs WHERE company_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM company_members WHERE company_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            orphaned_media = sqlx::query_scalar!("SELECT storage_key FROM company_media WHERE company_id = ?", id)
+                .fetch_all(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM company_media WHERE company_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM companies WHERE id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        TrashEntity::Users => {
+            // Jobs and companies outlive their creator; only the link is dropped
+            sqlx::query!(
+                "DELETE h FROM application_status_history h JOIN applications a ON h.application_id = a.id WHERE a.user_id = ?",
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!("UPDATE application_status_history SET changed_by = NULL WHERE changed_by = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM applications WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM refresh_tokens WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM sessions WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM user_tokens WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM login_attempts WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM api_keys WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM user_identities WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            // Audit records of support sessions as this user go with them; ones
+            // where they were the acting admin keep the purge blocked
+            sqlx::query!(
+                "DELETE r FROM impersonation_requests r JOIN impersonations i ON r.impersonation_id = i.id WHERE i.target_user_id = ?",
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!("DELETE FROM impersonations WHERE target_user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM company_members WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("UPDATE jobs SET user_id = NULL WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("UPDATE companies SET user_id = NULL WHERE user_id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query!("DELETE FROM users WHERE id = ?", id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(orphaned_media)
 }
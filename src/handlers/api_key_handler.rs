@@ -0,0 +1,130 @@
+// src/handlers/api_key_handler.rs
+use actix_web::{web, HttpResponse};
+use sqlx::MySqlPool;
+use chrono::{Duration, Utc};
+use crate::errors::ApiError;
+use crate::middleware::extractors::{RequireRole, Employer};
+use crate::models::api_key::{ApiKey, ApiKeyView, ApiScope, CreateApiKey, CreatedApiKey};
+use crate::models::user::UserRole;
+use crate::utils::tokens::{generate_token, hash_token};
+use crate::utils::validation::FieldErrors;
+
+/// Longest expiry a key may be given; omit `expires_in_days` for no expiry
+const MAX_KEY_LIFETIME_DAYS: i64 = 3650;
+
+/// Create an API key for the caller (POST /api-keys). The key acts as its owner,
+/// limited to the requested scopes.
+pub async fn create_api_key(
+    user: RequireRole<Employer>,
+    db: web::Data<MySqlPool>,
+    info: web::Json<CreateApiKey>,
+) -> Result<HttpResponse, ApiError> {
+    if user.role != UserRole::Employer {
+        return Err(ApiError::forbidden("Only employer accounts can create API keys"));
+    }
+
+    let name = info.name.trim();
+    let mut errors = FieldErrors::default();
+    if name.is_empty() || name.chars().count() > 100 {
+        errors.add("name", "must be between 1 and 100 characters");
+    }
+    if info.scopes.is_empty() {
+        errors.add("scopes", "must list at least one scope");
+    }
+    match info.expires_in_days {
+        Some(days) if days < 1 => errors.add("expires_in_days", "must be at least 1"),
+        Some(days) if days > MAX_KEY_LIFETIME_DAYS => errors.add(
+            "expires_in_days",
+            format!("must be at most {}", MAX_KEY_LIFETIME_DAYS),
+        ),
+        _ => {}
+    }
+    errors.into_result("Invalid API key details")?;
+
+    let mut scopes: Vec<ApiScope> = vec![];
+    for scope in &info.scopes {
+        if !scopes.contains(scope) {
+            scopes.push(*scope);
+        }
+    }
+
+    // `jbk_<8 hex>` stays visible for identification; the whole key is only hashed
+    let secret = generate_token();
+    let prefix = format!("jbk_{}", &secret[..8]);
+    let key = format!("{}_{}", prefix, &secret[8..]);
+
+    let now = Utc::now().naive_utc();
+    let expires_at = info.expires_in_days.map(|days| now + Duration::days(days));
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO api_keys (user_id, name, prefix, key_hash, scopes, created_at, expires_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+        user.sub,
+        name,
+        prefix,
+        hash_token(&key),
+        ApiScope::join(&scopes),
+        now,
+        expires_at
+    )
+    .execute(db.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Created().json(CreatedApiKey {
+        key,
+        details: ApiKeyView {
+            id: result.last_insert_id() as i32,
+            name: name.to_string(),
+            prefix,
+            scopes,
+            created_at: Some(now),
+            last_used_at: None,
+            expires_at,
+            revoked_at: None,
+        },
+    }))
+}
+
+/// List the caller's API keys, revoked ones included (GET /api-keys)
+pub async fn list_api_keys(
+    user: RequireRole<Employer>,
+    db: web::Data<MySqlPool>,
+) -> Result<HttpResponse, ApiError> {
+    let keys: Vec<ApiKey> = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, name, prefix, scopes, created_at, last_used_at, expires_at, revoked_at
+        FROM api_keys WHERE user_id = ? ORDER BY created_at DESC
+        "#,
+        user.sub
+    )
+    .fetch_all(db.get_ref())
+    .await?;
+
+    let keys: Vec<ApiKeyView> = keys.into_iter().map(ApiKeyView::from).collect();
+    Ok(HttpResponse::Ok().json(keys))
+}
+
+/// Revoke one of the caller's API keys (DELETE /api-keys/{id})
+pub async fn revoke_api_key(
+    user: RequireRole<Employer>,
+    db: web::Data<MySqlPool>,
+    key_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let result = sqlx::query!(
+        "UPDATE api_keys SET revoked_at = ? WHERE id = ? AND user_id = ? AND revoked_at IS NULL",
+        Utc::now().naive_utc(),
+        *key_id,
+        user.sub
+    )
+    .execute(db.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("API key not found"));
+    }
+
+    Ok(HttpResponse::Ok().body("API key revoked"))
+}
@@ -1,208 +1,391 @@
-use actix_web::{post, web, get, HttpResponse, HttpMessage, HttpRequest, Responder};
+use actix_web::{post, patch, web, get, HttpResponse};
 use sqlx::MySqlPool;
-use crate::models::application::{Application, ApplyRequest, ApplicationRecord, ApplyJob, JobApplicationView};
-use crate::middleware::auth_middleware::Claims;
-use chrono::Utc;
+use crate::errors::ApiError;
+use crate::models::application::{
+    ApplicationRecord, ApplyJob, JobApplicationView, ApplicationStatus,
+    UpdateApplicationStatus, WithdrawApplication,
+};
+use crate::middleware::auth_middleware::{has_job_access, ensure_email_verified};
+use crate::middleware::extractors::{AuthUser, RequireRole, Admin};
+use crate::models::company::CompanyRole;
+use crate::handlers::job_handler::LIVE_JOBS_SQL;
+use crate::models::job::JobStatus;
+use chrono::{NaiveDateTime, Utc};
+use serde_json::json;
+use std::env;
+
+/// Whether a candidate who withdrew may apply to the same job again
+/// (ALLOW_REAPPLY_AFTER_WITHDRAWAL, default true)
+fn reapply_after_withdrawal_allowed() -> bool {
+    env::var("ALLOW_REAPPLY_AFTER_WITHDRAWAL")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+}
 
 #[post("/applications/jobs/{job_id}/apply")]
 pub async fn apply_to_job(
-    req: HttpRequest,
+    user: AuthUser,
     db: web::Data<MySqlPool>,
     job_id: web::Path<i32>,
     form: web::Json<ApplyJob>,
-) -> impl Responder {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return HttpResponse::Unauthorized().body("Unauthorized"),
-    };
-
-    let user_id = claims.sub;
+) -> Result<HttpResponse, ApiError> {
+    let user_id = user.sub;
     let job_id = *job_id;
+    ensure_email_verified(db.get_ref(), &user).await?;
 
-    // 🔍 Check if job exists
-    match sqlx::query!("SELECT id FROM jobs WHERE id = ?", job_id)
-        .fetch_optional(db.get_ref())
-        .await
-    {
-        Ok(None) => return HttpResponse::NotFound().body("Job not found"),
-        Err(e) => {
-            eprintln!("Database error checking job existence: {:?}", e);
-            return HttpResponse::InternalServerError().body("Internal server error");
-        }
-        _ => {}
+    // 🔍 Check the job exists and is open for applications
+    let (status, expires_at) = sqlx::query_as::<_, (String, Option<NaiveDateTime>)>(&format!(
+        "SELECT j.status, j.expires_at {} AND j.id = ?",
+        LIVE_JOBS_SQL
+    ))
+    .bind(job_id)
+    .fetch_optional(db.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Job not found"))?;
+
+    let expired = expires_at.is_some_and(|deadline| deadline <= Utc::now().naive_utc());
+    if status != JobStatus::Published.as_str() || expired {
+        return Err(ApiError::conflict("This job is not accepting applications"));
     }
 
     // 🔄 Check for duplicate application
-    match sqlx::query!(
-        "SELECT id FROM applications WHERE user_id = ? AND job_id = ?",
-        user_id,
-        job_id
+    let previous = match sqlx::query_as::<_, (i32, String)>(
+        "SELECT id, status FROM applications WHERE user_id = ? AND job_id = ?",
     )
+    .bind(user_id)
+    .bind(job_id)
     .fetch_optional(db.get_ref())
-    .await
+    .await?
     {
-        Ok(Some(_)) => return HttpResponse::BadRequest().body("You have already applied to this job"),
-        Err(e) => {
-            eprintln!("Database error checking duplicate application: {:?}", e);
-            return HttpResponse::InternalServerError().body("Internal server error");
+        Some((_, status)) if status != ApplicationStatus::Withdrawn.as_str() => {
+            return Err(ApiError::bad_request("You have already applied to this job"));
         }
-        _ => {}
-    }
+        Some(_) if !reapply_after_withdrawal_allowed() => {
+            return Err(ApiError::conflict("You withdrew from this job and cannot reapply"));
+        }
+        previous => previous.map(|(id, _)| id),
+    };
+
+    let now = Utc::now().naive_utc();
+    let mut tx = db.begin().await?;
 
-    // ✅ Insert application
-    let result = sqlx::query!(
+    let application_id = match previous {
+        // ♻️ Reapplying reopens the withdrawn application as a fresh submission,
+        // unless a concurrent reapply already did
+        Some(id) => {
+            let reopened = sqlx::query!(
+                r#"
+                UPDATE applications
+                SET full_name = ?, email = ?, resume_link = ?, cover_letter = ?, applied_at = ?,
+                    status = ?, status_updated_at = ?, withdrawn_at = NULL, withdrawal_reason = NULL
+                WHERE id = ? AND status = ?
+                "#,
+                form.full_name,
+                form.email,
+                form.resume_link,
+                form.cover_letter,
+                now,
+                ApplicationStatus::Applied.as_str(),
+                now,
+                id,
+                ApplicationStatus::Withdrawn.as_str()
+            )
+            .execute(&mut *tx)
+            .await?;
+            if reopened.rows_affected() == 0 {
+                return Err(ApiError::conflict("You have already applied to this job"));
+            }
+            id as u64
+        }
+        // ✅ Insert application
+        None => {
+            sqlx::query!(
+                r#"
+                INSERT INTO applications (user_id, job_id, full_name, email, resume_link, cover_letter, applied_at, status)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+                user_id,
+                job_id,
+                form.full_name,
+                form.email,
+                form.resume_link,
+                form.cover_letter,
+                now,
+                ApplicationStatus::Applied.as_str()
+            )
+            .execute(&mut *tx)
+            .await?
+            .last_insert_id()
+        }
+    };
+
+    // 📜 The pipeline history starts with the submission itself
+    let from_status = previous.map(|_| ApplicationStatus::Withdrawn.as_str());
+    sqlx::query!(
         r#"
-        INSERT INTO applications (user_id, job_id, full_name, email, resume_link, cover_letter, applied_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO application_status_history (application_id, from_status, to_status, changed_by, changed_at)
+        VALUES (?, ?, ?, ?, ?)
         "#,
+        application_id,
+        from_status,
+        ApplicationStatus::Applied.as_str(),
         user_id,
-        job_id,
-        form.full_name,
-        form.email,
-        form.resume_link,
-        form.cover_letter,
-        Utc::now().naive_utc()
+        now
     )
-    .execute(db.get_ref())
-    .await;
-
-    match result {
-        Ok(_) => HttpResponse::Created().body("Application submitted"),
-        Err(e) => {
-            eprintln!("Error applying to job: {:?}", e);
-            HttpResponse::InternalServerError().body("Failed to submit application")
-        }
-    }
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(HttpResponse::Created().body("Application submitted"))
 }
 
 #[get("/applications")]
 pub async fn list_applications(
-    req: HttpRequest,
+    _admin: RequireRole<Admin>,
     db: web::Data<MySqlPool>,
-) -> impl Responder {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return HttpResponse::Unauthorized().body("Missing token"),
-    };
-
-    if claims.role != "admin" {
-        return HttpResponse::Forbidden().body("Admins only");
-    }
-
-    let result = sqlx::query_as!(
+) -> Result<HttpResponse, ApiError> {
+    let applications: Vec<ApplicationRecord> = sqlx::query_as!(
         ApplicationRecord,
         r#"
-        SELECT 
+        SELECT
             a.id, a.user_id, a.job_id, a.applied_at,
-            a.resume_link,
+            a.resume_link, a.status,
             u.email AS user_email,
             j.title AS job_title
         FROM applications a
         JOIN users u ON a.user_id = u.id
         JOIN jobs j ON a.job_id = j.id
+        WHERE u.deleted_at IS NULL AND j.deleted_at IS NULL
         ORDER BY a.applied_at DESC
         "#
     )
     .fetch_all(db.get_ref())
-    .await;
+    .await?;
 
-    match result {
-        Ok(applications) => HttpResponse::Ok().json(applications),
-        Err(e) => {
-            eprintln!("Error fetching applications: {:?}", e);
-            HttpResponse::InternalServerError().body("Failed to load applications")
-        }
-    }
+    Ok(HttpResponse::Ok().json(applications))
 }
 
 #[get("/applications/me")]
 pub async fn get_my_applications(
-    req: HttpRequest,
+    user: AuthUser,
     db: web::Data<MySqlPool>,
-) -> impl Responder {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return HttpResponse::Unauthorized().body("Unauthorized"),
-    };
-
-    let user_id = claims.sub;
+) -> Result<HttpResponse, ApiError> {
+    let user_id = user.sub;
 
-    let result = sqlx::query_as!(
+    let applications: Vec<ApplicationRecord> = sqlx::query_as!(
         ApplicationRecord,
         r#"
-        SELECT 
+        SELECT
             a.id, a.user_id, a.job_id, a.applied_at,
-            a.resume_link,
+            a.resume_link, a.status,
             u.email AS user_email,
             j.title AS job_title
         FROM applications a
         JOIN users u ON a.user_id = u.id
         JOIN jobs j ON a.job_id = j.id
-        WHERE a.user_id = ?
+        WHERE a.user_id = ? AND j.deleted_at IS NULL
         ORDER BY a.applied_at DESC
         "#,
         user_id
     )
     .fetch_all(db.get_ref())
-    .await;
+    .await?;
 
-    match result {
-        Ok(applications) => HttpResponse::Ok().json(applications),
-        Err(e) => {
-            eprintln!("Error fetching user applications: {:?}", e);
-            HttpResponse::InternalServerError().body("Failed to fetch your applications")
-        }
-    }
+    Ok(HttpResponse::Ok().json(applications))
 }
 
 #[get("/applications/jobs/{job_id}")]
 pub async fn get_applications_for_job(
-    req: HttpRequest,
+    user: AuthUser,
     db: web::Data<MySqlPool>,
     job_id: web::Path<i32>,
-) -> impl Responder {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return HttpResponse::Unauthorized().body("Unauthorized"),
-    };
+) -> Result<HttpResponse, ApiError> {
+    let job_id = *job_id;
 
-    // Only admin users allowed
-    if claims.role != "admin" {
-        return HttpResponse::Forbidden().body("Admins only");
-    }
+    // Admins, the job's poster and any member of its company may view applicants
+    let (job_user_id, job_company_id) = sqlx::query_as::<_, (Option<i32>, Option<i32>)>(
+        "SELECT user_id, company_id FROM jobs WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(job_id)
+    .fetch_optional(db.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Job not found"))?;
 
-    let job_id = *job_id;
+    if !has_job_access(db.get_ref(), &user, job_user_id, job_company_id, CompanyRole::Viewer).await? {
+        return Err(ApiError::forbidden("Access denied"));
+    }
 
     // Fetch all applications for this job
-    let result = sqlx::query_as!(
+    let applications: Vec<JobApplicationView> = sqlx::query_as!(
         JobApplicationView,
         r#"
-        SELECT 
+        SELECT
             a.id,
             a.full_name,
             a.email,
             a.resume_link,
             a.cover_letter,
             a.applied_at,
-            a.job_id
+            a.job_id,
+            a.status
         FROM applications a
-        WHERE a.job_id = ?
+        JOIN users u ON a.user_id = u.id
+        WHERE a.job_id = ? AND u.deleted_at IS NULL AND a.status <> 'withdrawn'
         ORDER BY a.applied_at DESC
         "#,
         job_id
     )
     .fetch_all(db.get_ref())
-    .await;
+    .await?;
 
-    match result {
-        Ok(applications) => HttpResponse::Ok().json(applications),
-        Err(e) => {
-            eprintln!("Error fetching applications for job: {:?}", e);
-            HttpResponse::InternalServerError().body("Failed to load applications for this job")
-        }
+    Ok(HttpResponse::Ok().json(applications))
+}
+
+#[patch("/applications/{id}/status")]
+pub async fn update_application_status(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    application_id: web::Path<i32>,
+    body: web::Json<UpdateApplicationStatus>,
+) -> Result<HttpResponse, ApiError> {
+    let application_id = *application_id;
+
+    let (current, job_user_id, job_company_id) = sqlx::query_as::<_, (String, Option<i32>, Option<i32>)>(
+        r#"
+        SELECT a.status, j.user_id, j.company_id
+        FROM applications a
+        JOIN jobs j ON a.job_id = j.id
+        WHERE a.id = ? AND j.deleted_at IS NULL
+        "#,
+    )
+    .bind(application_id)
+    .fetch_optional(db.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Application not found"))?;
+
+    // Only whoever manages the job (poster, company recruiters, admins) moves candidates
+    if !has_job_access(db.get_ref(), &user, job_user_id, job_company_id, CompanyRole::Recruiter).await? {
+        return Err(ApiError::forbidden("Access denied"));
+    }
+
+    let next = body.status;
+    if next == ApplicationStatus::Withdrawn {
+        return Err(ApiError::bad_request("Only the applicant can withdraw an application"));
     }
+
+    let allowed = current
+        .parse::<ApplicationStatus>()
+        .is_ok_and(|status| status.can_transition_to(next));
+    if !allowed {
+        return Err(ApiError::conflict(format!(
+            "Cannot move an application from {} to {}",
+            current,
+            next.as_str()
+        )));
+    }
+
+    let now = Utc::now().naive_utc();
+    let mut tx = db.begin().await?;
+
+    // Guard on the status we validated against so concurrent changes can't skip a stage
+    let updated = sqlx::query!(
+        "UPDATE applications SET status = ?, status_updated_at = ? WHERE id = ? AND status = ?",
+        next.as_str(),
+        now,
+        application_id,
+        current
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(ApiError::conflict("Application status changed concurrently; retry"));
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO application_status_history (application_id, from_status, to_status, changed_by, note, changed_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        application_id,
+        current,
+        next.as_str(),
+        user.sub,
+        body.note,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(json!({ "id": application_id, "status": next.as_str() })))
+}
+
+#[post("/applications/{id}/withdraw")]
+pub async fn withdraw_application(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    application_id: web::Path<i32>,
+    body: Option<web::Json<WithdrawApplication>>,
+) -> Result<HttpResponse, ApiError> {
+    let application_id = *application_id;
+    let reason = body.and_then(|b| b.into_inner().reason);
+
+    // Applicants can only see their own applications; anyone else gets a 404
+    let current = sqlx::query_scalar::<_, String>(
+        "SELECT status FROM applications WHERE id = ? AND user_id = ?",
+    )
+    .bind(application_id)
+    .bind(user.sub)
+    .fetch_optional(db.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Application not found"))?;
+
+    if !current.parse::<ApplicationStatus>().is_ok_and(|status| status.can_withdraw()) {
+        return Err(ApiError::conflict(format!("Cannot withdraw an application that is {}", current)));
+    }
+
+    let now = Utc::now().naive_utc();
+    let mut tx = db.begin().await?;
+
+    let updated = sqlx::query!(
+        r#"
+        UPDATE applications
+        SET status = ?, status_updated_at = ?, withdrawn_at = ?, withdrawal_reason = ?
+        WHERE id = ? AND status = ?
+        "#,
+        ApplicationStatus::Withdrawn.as_str(),
+        now,
+        now,
+        reason,
+        application_id,
+        current
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(ApiError::conflict("Application status changed concurrently; retry"));
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO application_status_history (application_id, from_status, to_status, changed_by, note, changed_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        application_id,
+        current,
+        ApplicationStatus::Withdrawn.as_str(),
+        user.sub,
+        reason,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(json!({
+        "id": application_id,
+        "status": ApplicationStatus::Withdrawn.as_str(),
+    })))
 }
@@ -1,18 +1,30 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use sqlx::MySqlPool;
-use rand::Rng;
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
-use argon2::password_hash::{SaltString, rand_core::OsRng};
-use jsonwebtoken::{encode, Header, EncodingKey};
 use chrono::{Utc, Duration};
 use std::env;
-use crate::middleware::auth_middleware::Claims;
+use crate::errors::ApiError;
+use crate::middleware::auth_middleware::{Actor, Claims};
+use crate::models::session::ClientInfo;
+use crate::models::user::UserRole;
+use crate::models::auth::{
+    RefreshToken, RefreshRequest, LogoutRequest, TokenPairResponse, TokenPurpose, VerifyEmailRequest,
+    ResendVerificationRequest, ForgotPasswordRequest, ResetPasswordRequest, MfaChallengeResponse,
+    MfaLoginRequest,
+};
+use crate::utils::jwt::JwtKeys;
+use crate::utils::mailer::{Email, Mailer};
+use crate::utils::passwords::{hash_password, verify_password, dummy_verify, needs_rehash};
+use crate::utils::tokens::{generate_token, hash_token};
+use crate::utils::totp::{self, normalize_recovery_code};
+use crate::utils::validation::{FieldErrors, PasswordPolicy, normalize_email, is_valid_email};
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
     pub email: String,
     pub password: String,
+    /// `job_seeker` (default) or `employer`; admins cannot self-register
+    pub role: Option<UserRole>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,100 +36,1115 @@ pub struct LoginRequest {
 #[derive(Debug, Serialize)]
 pub struct LoginSuccessResponse {
     pub message: String,
-    pub token: String,
+    #[serde(flatten)]
+    pub tokens: TokenPairResponse,
+}
+
+/// Lifetime of access tokens (ACCESS_TOKEN_TTL_MINUTES, default 15)
+fn access_token_ttl() -> Duration {
+    let minutes = env::var("ACCESS_TOKEN_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15);
+    Duration::minutes(minutes)
+}
+
+/// Lifetime of refresh tokens (REFRESH_TOKEN_TTL_DAYS, default 30)
+fn refresh_token_ttl() -> Duration {
+    let days = env::var("REFRESH_TOKEN_TTL_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    Duration::days(days)
+}
+
+/// Sign a short-lived access token bound to the session (token family) `sid`
+fn encode_access_token(
+    keys: &JwtKeys,
+    user_id: i32,
+    role: UserRole,
+    sid: &str,
+    mfa: bool,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let expiration = now
+        .checked_add_signed(access_token_ttl())
+        .expect("valid timestamp")
+        .timestamp() as usize;
+
+    let claims = Claims {
+        sub: user_id,
+        role,
+        exp: expiration,
+        iat: now.timestamp() as usize,
+        iss: keys.issuer.clone(),
+        aud: keys.audience.clone(),
+        sid: sid.to_string(),
+        mfa,
+        scopes: None,
+        act: None,
+    };
+
+    keys.encode(&claims)
+}
+
+/// Persist a new refresh token in `family_id` and return its plaintext value
+async fn insert_refresh_token<'e, E>(
+    executor: E,
+    user_id: i32,
+    family_id: &str,
+    mfa: bool,
+) -> Result<String, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::MySql>,
+{
+    let token = generate_token();
+    let expires_at = (Utc::now() + refresh_token_ttl()).naive_utc();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at, created_at, mfa)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        user_id,
+        family_id,
+        hash_token(&token),
+        expires_at,
+        Utc::now().naive_utc(),
+        mfa
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(token)
+}
+
+/// Revoke every session a user has, along with all of their refresh tokens
+pub async fn revoke_user_sessions(conn: &mut sqlx::MySqlConnection, user_id: i32) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!(
+        "UPDATE sessions SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL",
+        now,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL",
+        now,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Revoke a session and every refresh token in its family
+pub async fn revoke_token_family(db: &MySqlPool, family_id: &str) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!(
+        "UPDATE sessions SET revoked_at = ? WHERE family_id = ? AND revoked_at IS NULL",
+        now,
+        family_id
+    )
+    .execute(db)
+    .await?;
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = ? WHERE family_id = ? AND revoked_at IS NULL",
+        now,
+        family_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+async fn find_refresh_token(db: &MySqlPool, token: &str) -> Result<Option<RefreshToken>, sqlx::Error> {
+    sqlx::query_as::<_, RefreshToken>(
+        r#"
+        SELECT id, user_id, family_id, expires_at, used_at, revoked_at, mfa
+        FROM refresh_tokens WHERE token_hash = ?
+        "#
+    )
+    .bind(hash_token(token))
+    .fetch_optional(db)
+    .await
+}
+
+/// Lifetime of email verification links (EMAIL_VERIFICATION_TTL_HOURS, default 24)
+fn email_verification_ttl() -> Duration {
+    let hours = env::var("EMAIL_VERIFICATION_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(24);
+    Duration::hours(hours)
+}
+
+/// Lifetime of password reset links (PASSWORD_RESET_TTL_MINUTES, default 60)
+fn password_reset_ttl() -> Duration {
+    let minutes = env::var("PASSWORD_RESET_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+    Duration::minutes(minutes)
+}
+
+/// Lifetime of the challenge between password and TOTP steps (MFA_CHALLENGE_TTL_MINUTES, default 5)
+fn mfa_challenge_ttl() -> Duration {
+    let minutes = env::var("MFA_CHALLENGE_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5);
+    Duration::minutes(minutes)
+}
+
+/// Base URL used to build links in outgoing mail (APP_BASE_URL)
+fn app_base_url() -> String {
+    env::var("APP_BASE_URL").unwrap_or_else(|_| "http://localhost:8000".to_string())
+}
+
+/// Persist a single-use token for `purpose` and return its plaintext value
+async fn issue_user_token<'e, E>(
+    executor: E,
+    user_id: i32,
+    purpose: TokenPurpose,
+    ttl: Duration,
+) -> Result<String, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::MySql>,
+{
+    let token = generate_token();
+    let now = Utc::now();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO user_tokens (user_id, purpose, token_hash, expires_at, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        user_id,
+        purpose.as_str(),
+        hash_token(&token),
+        (now + ttl).naive_utc(),
+        now.naive_utc()
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(token)
+}
+
+/// Mark a live token for `purpose` as used and return its owner. Only the first
+/// caller wins, so a token can never be redeemed twice.
+async fn consume_user_token(
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+    token: &str,
+    purpose: TokenPurpose,
+) -> Result<Option<i32>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let token_hash = hash_token(token);
+
+    let consumed = sqlx::query!(
+        r#"
+        UPDATE user_tokens SET used_at = ?
+        WHERE token_hash = ? AND purpose = ? AND used_at IS NULL AND expires_at > ?
+        "#,
+        now,
+        token_hash,
+        purpose.as_str(),
+        now
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    if consumed.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    sqlx::query_scalar!("SELECT user_id FROM user_tokens WHERE token_hash = ?", token_hash)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+/// Issue a verification token and mail the link. Delivery failures are logged,
+/// not surfaced: the user can always ask for another link.
+pub async fn send_verification_email(
+    db: &MySqlPool,
+    mailer: &dyn Mailer,
+    user_id: i32,
+    email: &str,
+) -> Result<(), ApiError> {
+    let token = issue_user_token(db, user_id, TokenPurpose::EmailVerification, email_verification_ttl()).await?;
+
+    let message = Email {
+        to: email.to_string(),
+        subject: "Verify your email address".to_string(),
+        body: format!(
+            "Confirm your address by opening the link below:\n\n{}/verify-email?token={}\n\n\
+             The link expires in {} hours. If you did not sign up, ignore this email.",
+            app_base_url(),
+            token,
+            email_verification_ttl().num_hours()
+        ),
+    };
+
+    if let Err(e) = mailer.send(&message) {
+        eprintln!("Error sending verification email to user {}: {}", user_id, e);
+    }
+
+    Ok(())
 }
 
 // 🔹 LOGIC HANDLER for registration
 pub async fn register_user_handler(
     db: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
     info: web::Json<RegisterRequest>,
-) -> impl Responder {
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let hash = argon2
-        .hash_password(info.password.as_bytes(), &salt)
-        .unwrap()
-        .to_string();
-
-    let result = sqlx::query!(
-        "INSERT INTO users (email, password_hash, role) VALUES (?, ?, 'user')",
-        info.email,
+) -> Result<HttpResponse, ApiError> {
+    let role = info.role.unwrap_or(UserRole::JobSeeker);
+    if role == UserRole::Admin {
+        return Err(ApiError::forbidden("Cannot register as admin"));
+    }
+
+    // 🔍 Validate every field up front so the client sees all problems at once
+    let email = normalize_email(&info.email);
+    let mut errors = FieldErrors::default();
+    if !is_valid_email(&email) {
+        errors.add("email", "must be a valid email address");
+    }
+    for problem in PasswordPolicy::from_env().violations(&info.password) {
+        errors.add("password", problem);
+    }
+    errors.into_result("Invalid registration details")?;
+
+    let taken = sqlx::query_scalar!("SELECT id FROM users WHERE email = ?", email)
+        .fetch_optional(db.get_ref())
+        .await?;
+    if taken.is_some() {
+        return Err(ApiError::conflict("Email is already registered"));
+    }
+
+    let hash = hash_password(&info.password)?;
+
+    // The unique key still catches a concurrent registration of the same address
+    let user_id = sqlx::query!(
+        "INSERT INTO users (email, password_hash, role) VALUES (?, ?, ?)",
+        email,
         hash,
+        role.as_str(),
     )
     .execute(db.get_ref())
-    .await;
+    .await
+    .map_err(|e| match ApiError::from(e) {
+        ApiError::Conflict(_) => ApiError::conflict("Email is already registered"),
+        other => other,
+    })?
+    .last_insert_id() as i32;
 
-    match result {
-        Ok(_) => HttpResponse::Created().body("User registered"),
-        Err(_) => HttpResponse::InternalServerError().body("Registration failed"),
-    }
+    send_verification_email(db.get_ref(), mailer.get_ref(), user_id, &email).await?;
+
+    Ok(HttpResponse::Created().body("User registered"))
 }
 
 // 🔹 ROUTE HANDLER for registration
 #[post("/register")]
 pub async fn register_user(
     db: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
     info: web::Json<RegisterRequest>,
 ) -> impl Responder {
-    register_user_handler(db, info).await
+    register_user_handler(db, mailer, info).await
+}
+
+// 🔹 LOGIC HANDLER for email verification — redeems a single-use token
+pub async fn verify_email_handler(
+    db: web::Data<MySqlPool>,
+    info: web::Json<VerifyEmailRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let mut tx = db.begin().await?;
+
+    let user_id = consume_user_token(&mut tx, &info.token, TokenPurpose::EmailVerification)
+        .await?
+        .ok_or_else(|| ApiError::bad_request("Invalid or expired verification token"))?;
+
+    sqlx::query!(
+        "UPDATE users SET email_verified_at = ? WHERE id = ? AND email_verified_at IS NULL",
+        Utc::now().naive_utc(),
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().body("Email verified"))
+}
+
+// 🔹 ROUTE HANDLER for email verification
+#[post("/verify-email")]
+pub async fn verify_email(
+    db: web::Data<MySqlPool>,
+    info: web::Json<VerifyEmailRequest>,
+) -> impl Responder {
+    verify_email_handler(db, info).await
+}
+
+/// Minimum gap between two verification or reset emails to the same account
+const RESEND_COOLDOWN_SECONDS: i64 = 60;
+
+/// Whether a `purpose` token was sent to the user within the resend cooldown
+async fn token_recently_sent(db: &MySqlPool, user_id: i32, purpose: TokenPurpose) -> Result<bool, sqlx::Error> {
+    let cooldown_start = (Utc::now() - Duration::seconds(RESEND_COOLDOWN_SECONDS)).naive_utc();
+    let recent = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM user_tokens WHERE user_id = ? AND purpose = ? AND created_at > ?",
+        user_id,
+        purpose.as_str(),
+        cooldown_start
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(recent > 0)
+}
+
+// 🔹 LOGIC HANDLER for resending verification — always answers the same way so
+// it cannot be used to discover which addresses are registered
+pub async fn resend_verification_handler(
+    db: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
+    info: web::Json<ResendVerificationRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let email = normalize_email(&info.email);
+    let response = HttpResponse::Accepted()
+        .body("If the address belongs to an unverified account, a new link is on its way");
+
+    let user_id = sqlx::query_scalar!(
+        "SELECT id FROM users WHERE email = ? AND deleted_at IS NULL AND email_verified_at IS NULL",
+        email
+    )
+    .fetch_optional(db.get_ref())
+    .await?;
+
+    let Some(user_id) = user_id else {
+        return Ok(response);
+    };
+
+    if !token_recently_sent(db.get_ref(), user_id, TokenPurpose::EmailVerification).await? {
+        send_verification_email(db.get_ref(), mailer.get_ref(), user_id, &email).await?;
+    }
+
+    Ok(response)
+}
+
+// 🔹 ROUTE HANDLER for resending verification
+#[post("/resend-verification")]
+pub async fn resend_verification(
+    db: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
+    info: web::Json<ResendVerificationRequest>,
+) -> impl Responder {
+    resend_verification_handler(db, mailer, info).await
+}
+
+/// Sign an access token, logging (not leaking) signing failures
+fn sign_access_token(
+    keys: &JwtKeys,
+    user_id: i32,
+    role: UserRole,
+    sid: &str,
+    mfa: bool,
+) -> Result<String, ApiError> {
+    encode_access_token(keys, user_id, role, sid, mfa).map_err(|e| {
+        eprintln!("Error signing access token: {:?}", e);
+        ApiError::Internal
+    })
+}
+
+/// Sign a time-boxed access token that lets `actor` act as `user_id`. It has no
+/// refresh token; the `impersonations` row it points to is its session.
+pub fn sign_impersonation_token(
+    keys: &JwtKeys,
+    user_id: i32,
+    role: UserRole,
+    actor: Actor,
+    ttl: Duration,
+) -> Result<String, ApiError> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: user_id,
+        role,
+        exp: (now + ttl).timestamp() as usize,
+        iat: now.timestamp() as usize,
+        iss: keys.issuer.clone(),
+        aud: keys.audience.clone(),
+        sid: format!("impersonation:{}", actor.impersonation_id),
+        mfa: false,
+        scopes: None,
+        act: Some(actor),
+    };
+
+    keys.encode(&claims).map_err(|e| {
+        eprintln!("Error signing impersonation token: {:?}", e);
+        ApiError::Internal
+    })
+}
+
+/// Open a new session (token family) for the client and hand out its first token pair
+async fn start_session(
+    db: &MySqlPool,
+    keys: &JwtKeys,
+    user_id: i32,
+    role: UserRole,
+    mfa: bool,
+    client: &ClientInfo,
+) -> Result<TokenPairResponse, ApiError> {
+    let family_id = generate_token();
+    let now = Utc::now().naive_utc();
+
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO sessions (user_id, family_id, user_agent, ip_address, created_at, last_seen_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        user_id,
+        family_id,
+        client.user_agent,
+        client.ip,
+        now,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+    let refresh_token = insert_refresh_token(&mut *tx, user_id, &family_id, mfa).await?;
+    tx.commit().await?;
+
+    let token = sign_access_token(keys, user_id, role, &family_id, mfa)?;
+
+    Ok(TokenPairResponse {
+        token,
+        refresh_token,
+        token_type: "Bearer".to_string(),
+        expires_in: access_token_ttl().num_seconds(),
+    })
+}
+
+/// Failed logins before an account is locked (LOGIN_MAX_FAILED_ATTEMPTS, default 5)
+fn max_failed_logins() -> i32 {
+    env::var("LOGIN_MAX_FAILED_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5)
+}
+
+/// Failed logins one IP may make per window (LOGIN_MAX_FAILED_PER_IP, default 20,
+/// over LOGIN_IP_WINDOW_MINUTES, default 15)
+fn ip_failure_limit() -> (i64, Duration) {
+    let limit = env::var("LOGIN_MAX_FAILED_PER_IP")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(20);
+    let minutes = env::var("LOGIN_IP_WINDOW_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15);
+    (limit, Duration::minutes(minutes))
+}
+
+/// How long an account stays locked after `failures` consecutive failed logins.
+/// Starts at LOGIN_LOCKOUT_MINUTES (default 15) and doubles with every further
+/// failure, capped at a day.
+fn lockout_duration(failures: i32) -> Option<Duration> {
+    let over = failures - max_failed_logins();
+    if over < 0 {
+        return None;
+    }
+
+    let base: i64 = env::var("LOGIN_LOCKOUT_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15);
+    let minutes = base.saturating_mul(1 << over.min(10)).min(24 * 60);
+    Some(Duration::minutes(minutes))
+}
+
+/// Refuse addresses that keep failing, whichever accounts they target. Requests
+/// without a known address share one bucket (`<=>` matches NULL).
+async fn check_ip_throttle(db: &MySqlPool, ip: Option<&str>) -> Result<(), ApiError> {
+    let (ip_limit, ip_window) = ip_failure_limit();
+    let ip_failures = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM login_attempts WHERE ip_address <=> ? AND succeeded = FALSE AND attempted_at > ?",
+        ip,
+        Utc::now().naive_utc() - ip_window
+    )
+    .fetch_one(db)
+    .await?;
+
+    if ip_failures >= ip_limit {
+        return Err(ApiError::too_many_requests("Too many failed login attempts; try again later"));
+    }
+    Ok(())
+}
+
+/// Count a failed password or second factor against the account, locking it once
+/// the limit is reached. The counter is read under a row lock so concurrent
+/// guesses each count.
+async fn register_failed_login(
+    db: &MySqlPool,
+    user_id: i32,
+    email: &str,
+    ip: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let failed_login_count = sqlx::query_scalar!(
+        "SELECT failed_login_count FROM users WHERE id = ? FOR UPDATE",
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let failures = failed_login_count + 1;
+    let locked_until = lockout_duration(failures).map(|d| Utc::now().naive_utc() + d);
+    sqlx::query!(
+        "UPDATE users SET failed_login_count = ?, locked_until = ? WHERE id = ?",
+        failures,
+        locked_until,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    record_login_attempt(db, Some(user_id), email, ip, false).await
+}
+
+/// Check a signed-in user's password before a sensitive change (new password,
+/// turning MFA off). Guesses go through the same throttle and lockout as login,
+/// so a stolen access token can't be used to brute-force the password. A wrong
+/// password is a 422 on `field`.
+pub async fn verify_current_password(
+    db: &MySqlPool,
+    user_id: i32,
+    password: &str,
+    field: &str,
+    client: &ClientInfo,
+) -> Result<(), ApiError> {
+    let ip = client.ip();
+    check_ip_throttle(db, ip).await?;
+
+    let user = sqlx::query!(
+        "SELECT email, password_hash, locked_until FROM users WHERE id = ? AND deleted_at IS NULL",
+        user_id
+    )
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| ApiError::not_found("User not found"))?;
+
+    if user.locked_until.is_some_and(|until| until > Utc::now().naive_utc()) {
+        return Err(ApiError::too_many_requests("Too many failed attempts; try again later"));
+    }
+
+    if !verify_password(password, &user.password_hash) {
+        register_failed_login(db, user_id, &user.email, ip).await?;
+        let mut errors = FieldErrors::default();
+        errors.add(field, "is incorrect");
+        return errors.into_result("Invalid password");
+    }
+    Ok(())
+}
+
+pub async fn record_login_attempt(
+    db: &MySqlPool,
+    user_id: Option<i32>,
+    email: &str,
+    ip: Option<&str>,
+    succeeded: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO login_attempts (user_id, email, ip_address, succeeded, attempted_at) VALUES (?, ?, ?, ?, ?)",
+        user_id,
+        email,
+        ip,
+        succeeded,
+        Utc::now().naive_utc()
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Replace `old_hash` with a hash under the current policy, unless the password
+/// changed concurrently
+async fn rehash_password(db: &MySqlPool, user_id: i32, old_hash: &str, password: &str) -> Result<(), ApiError> {
+    let hash = hash_password(password)?;
+    sqlx::query!(
+        "UPDATE users SET password_hash = ? WHERE id = ? AND password_hash = ?",
+        hash,
+        user_id,
+        old_hash
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
 }
 
 // 🔹 LOGIC HANDLER for login
 pub async fn login_user_handler(
+    req: HttpRequest,
     db: web::Data<MySqlPool>,
+    keys: web::Data<JwtKeys>,
     info: web::Json<LoginRequest>,
-) -> impl Responder {
-    let row = sqlx::query!(
-        "SELECT id, password_hash, role FROM users WHERE email = ?",
-        info.email
-    )
-    .fetch_one(db.get_ref())
-    .await;
-
-    match row {
-        Ok(user) => {
-            let parsed_hash = PasswordHash::new(&user.password_hash).unwrap();
-            let valid = Argon2::default()
-                .verify_password(info.password.as_bytes(), &parsed_hash)
-                .is_ok();
-
-            if valid {
-                let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-                let expiration = Utc::now()
-                    .checked_add_signed(Duration::hours(24))
-                    .expect("valid timestamp")
-                    .timestamp() as usize;
-
-                let claims = Claims {
-                    sub: user.id,
-                    role: user.role,
-                    exp: expiration,
-                };
-
-                let token = encode(
-                    &Header::default(),
-                    &claims,
-                    &EncodingKey::from_secret(secret.as_bytes()),
-                )
-                .unwrap();
-
-                HttpResponse::Ok().json(LoginSuccessResponse {
-                    message: "Login successful".to_string(),
-                    token,
-                })
-            } else {
-                HttpResponse::Unauthorized().body("Invalid credentials")
-            }
+) -> Result<HttpResponse, ApiError> {
+    let email = normalize_email(&info.email);
+    let client = ClientInfo::from_request(&req);
+    let ip = client.ip();
+    let now = Utc::now().naive_utc();
+
+    // ⛔ Throttle addresses that keep failing, whichever accounts they target
+    check_ip_throttle(db.get_ref(), ip).await?;
+
+    let user = sqlx::query!(
+        r#"
+        SELECT id, password_hash, role, locked_until, totp_enabled_at
+        FROM users WHERE email = ? AND deleted_at IS NULL
+        "#,
+        email
+    )
+    .fetch_optional(db.get_ref())
+    .await?;
+
+    let Some(user) = user else {
+        // Same work as a real check so unknown emails can't be told apart by timing
+        dummy_verify(&info.password);
+        record_login_attempt(db.get_ref(), None, &email, ip, false).await?;
+        return Err(ApiError::unauthorized("Invalid credentials"));
+    };
+
+    // 🔐 A locked account answers exactly like a wrong password, after the same
+    // hashing work, so lockouts don't reveal which emails are registered
+    let password_ok = verify_password(&info.password, &user.password_hash);
+    if user.locked_until.is_some_and(|until| until > now) {
+        record_login_attempt(db.get_ref(), Some(user.id), &email, ip, false).await?;
+        return Err(ApiError::unauthorized("Invalid credentials"));
+    }
+
+    if !password_ok {
+        register_failed_login(db.get_ref(), user.id, &email, ip).await?;
+        return Err(ApiError::unauthorized("Invalid credentials"));
+    }
+
+    // ♻️ Upgrade hashes made under an older algorithm or cost policy while we
+    // have the plaintext; a failure here must not block the login
+    if needs_rehash(&user.password_hash) {
+        if let Err(e) = rehash_password(db.get_ref(), user.id, &user.password_hash, &info.password).await {
+            eprintln!("Error upgrading password hash for user {}: {}", user.id, e);
         }
-        Err(_) => HttpResponse::Unauthorized().body("Invalid credentials"),
     }
+
+    // Unknown roles fall back to the least privileged one
+    let role = user.role.parse().unwrap_or(UserRole::JobSeeker);
+    complete_login(db.get_ref(), &keys, user.id, role, user.totp_enabled_at.is_some(), &email, &client).await
+}
+
+/// Everything after a successful first factor (password or external identity).
+/// With TOTP enabled this only earns a challenge; counters are left alone until
+/// the second factor passes, so codes can't be guessed between correct
+/// passwords. Otherwise the lockout is cleared and a new session (token family)
+/// is opened.
+pub async fn complete_login(
+    db: &MySqlPool,
+    keys: &JwtKeys,
+    user_id: i32,
+    role: UserRole,
+    totp_enabled: bool,
+    email: &str,
+    client: &ClientInfo,
+) -> Result<HttpResponse, ApiError> {
+    if totp_enabled {
+        let challenge_token = issue_user_token(db, user_id, TokenPurpose::MfaChallenge, mfa_challenge_ttl()).await?;
+        return Ok(HttpResponse::Ok().json(MfaChallengeResponse {
+            mfa_required: true,
+            challenge_token,
+            expires_in: mfa_challenge_ttl().num_seconds(),
+        }));
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE users SET failed_login_count = 0, locked_until = NULL
+        WHERE id = ? AND (failed_login_count > 0 OR locked_until IS NOT NULL)
+        "#,
+        user_id
+    )
+    .execute(db)
+    .await?;
+    record_login_attempt(db, Some(user_id), email, client.ip(), true).await?;
+
+    let tokens = start_session(db, keys, user_id, role, false, client).await?;
+
+    Ok(HttpResponse::Ok().json(LoginSuccessResponse {
+        message: "Login successful".to_string(),
+        tokens,
+    }))
 }
 
 // 🔹 ROUTE HANDLER for login
 #[post("/login")]
 pub async fn login_user(
+    req: HttpRequest,
     db: web::Data<MySqlPool>,
+    keys: web::Data<JwtKeys>,
     info: web::Json<LoginRequest>,
 ) -> impl Responder {
-    login_user_handler(db, info).await
+    login_user_handler(req, db, keys, info).await
+}
+
+/// Publish the verification keys (JWKS) so other services can check our access
+/// tokens. Shared HS256 secrets are never listed.
+pub async fn get_jwks(keys: web::Data<JwtKeys>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=300"))
+        .json(keys.jwks())
+}
+
+/// Redeem an unused recovery code, so each one works exactly once
+async fn consume_recovery_code(
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+    user_id: i32,
+    code: &str,
+) -> Result<bool, sqlx::Error> {
+    let consumed = sqlx::query!(
+        "UPDATE mfa_recovery_codes SET used_at = ? WHERE user_id = ? AND code_hash = ? AND used_at IS NULL",
+        Utc::now().naive_utc(),
+        user_id,
+        hash_token(&normalize_recovery_code(code))
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(consumed.rows_affected() == 1)
+}
+
+/// Record that TOTP `step` was used, refusing it if it (or a later one) already was
+pub async fn claim_totp_step<'e, E>(executor: E, user_id: i32, step: i64) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::MySql>,
+{
+    let claimed = sqlx::query!(
+        "UPDATE users SET totp_last_step = ? WHERE id = ? AND (totp_last_step IS NULL OR totp_last_step < ?)",
+        step,
+        user_id,
+        step
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(claimed.rows_affected() == 1)
+}
+
+// 🔹 LOGIC HANDLER for the second login step — trades the challenge token and a
+// TOTP or recovery code for the real token pair
+pub async fn login_mfa_handler(
+    req: HttpRequest,
+    db: web::Data<MySqlPool>,
+    keys: web::Data<JwtKeys>,
+    info: web::Json<MfaLoginRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let client = ClientInfo::from_request(&req);
+    let ip = client.ip();
+    let now = Utc::now().naive_utc();
+
+    check_ip_throttle(db.get_ref(), ip).await?;
+
+    let mut tx = db.begin().await?;
+
+    // The challenge is only spent once the second factor checks out; wrong codes
+    // roll back and count towards the account lockout instead
+    let user_id = consume_user_token(&mut tx, &info.challenge_token, TokenPurpose::MfaChallenge)
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("Invalid or expired challenge"))?;
+
+    let user = sqlx::query!(
+        r#"
+        SELECT email, role, locked_until, totp_secret, totp_last_step
+        FROM users WHERE id = ? AND deleted_at IS NULL AND totp_enabled_at IS NOT NULL
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::unauthorized("Invalid or expired challenge"))?;
+
+    // Locked accounts get the same answer as a wrong code
+    if user.locked_until.is_some_and(|until| until > now) {
+        tx.rollback().await?;
+        record_login_attempt(db.get_ref(), Some(user_id), &user.email, ip, false).await?;
+        return Err(ApiError::unauthorized("Invalid authentication code"));
+    }
+
+    let secret = user.totp_secret.as_deref().unwrap_or_default();
+    let verified = match (&info.code, &info.recovery_code) {
+        (Some(code), None) => match totp::verify_code(secret, code, user.totp_last_step)? {
+            Some(step) => claim_totp_step(&mut *tx, user_id, step).await?,
+            None => false,
+        },
+        (None, Some(recovery_code)) => consume_recovery_code(&mut tx, user_id, recovery_code).await?,
+        _ => return Err(ApiError::bad_request("Provide either code or recovery_code")),
+    };
+
+    if !verified {
+        tx.rollback().await?;
+        register_failed_login(db.get_ref(), user_id, &user.email, ip).await?;
+        return Err(ApiError::unauthorized("Invalid authentication code"));
+    }
+
+    sqlx::query!(
+        "UPDATE users SET failed_login_count = 0, locked_until = NULL WHERE id = ?",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    record_login_attempt(db.get_ref(), Some(user_id), &user.email, ip, true).await?;
+
+    let role = user.role.parse().unwrap_or(UserRole::JobSeeker);
+    let tokens = start_session(db.get_ref(), &keys, user_id, role, true, &client).await?;
+
+    Ok(HttpResponse::Ok().json(LoginSuccessResponse {
+        message: "Login successful".to_string(),
+        tokens,
+    }))
+}
+
+// 🔹 ROUTE HANDLER for the second login step
+#[post("/login/mfa")]
+pub async fn login_mfa(
+    req: HttpRequest,
+    db: web::Data<MySqlPool>,
+    keys: web::Data<JwtKeys>,
+    info: web::Json<MfaLoginRequest>,
+) -> impl Responder {
+    login_mfa_handler(req, db, keys, info).await
+}
+
+// 🔹 LOGIC HANDLER for refresh — rotates the refresh token on every use
+pub async fn refresh_token_handler(
+    db: web::Data<MySqlPool>,
+    keys: web::Data<JwtKeys>,
+    info: web::Json<RefreshRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let stored = find_refresh_token(db.get_ref(), &info.refresh_token)
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("Invalid refresh token"))?;
+
+    if stored.revoked_at.is_some() {
+        return Err(ApiError::unauthorized("Session has been revoked"));
+    }
+
+    // ⛔ A rotated token presented again means it leaked: kill the whole family
+    if stored.used_at.is_some() {
+        revoke_token_family(db.get_ref(), &stored.family_id).await?;
+        return Err(ApiError::unauthorized("Refresh token reuse detected; session revoked"));
+    }
+
+    if stored.expires_at <= Utc::now().naive_utc() {
+        return Err(ApiError::unauthorized("Refresh token expired"));
+    }
+
+    let role = sqlx::query_scalar!("SELECT role FROM users WHERE id = ? AND deleted_at IS NULL", stored.user_id)
+        .fetch_optional(db.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("Invalid refresh token"))?
+        .parse()
+        .unwrap_or(UserRole::JobSeeker);
+
+    let mut tx = db.begin().await?;
+
+    // Mark as used only if nobody beat us to it; a lost race counts as reuse
+    let marked = sqlx::query!(
+        "UPDATE refresh_tokens SET used_at = ? WHERE id = ? AND used_at IS NULL AND revoked_at IS NULL",
+        Utc::now().naive_utc(),
+        stored.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if marked.rows_affected() != 1 {
+        tx.rollback().await?;
+        revoke_token_family(db.get_ref(), &stored.family_id).await?;
+        return Err(ApiError::unauthorized("Refresh token reuse detected; session revoked"));
+    }
+
+    // The rotated token keeps the session's second-factor status
+    let refresh_token = insert_refresh_token(&mut *tx, stored.user_id, &stored.family_id, stored.mfa).await?;
+    tx.commit().await?;
+
+    let token = sign_access_token(&keys, stored.user_id, role, &stored.family_id, stored.mfa)?;
+
+    Ok(HttpResponse::Ok().json(TokenPairResponse {
+        token,
+        refresh_token,
+        token_type: "Bearer".to_string(),
+        expires_in: access_token_ttl().num_seconds(),
+    }))
+}
+
+// 🔹 ROUTE HANDLER for refresh
+#[post("/refresh")]
+pub async fn refresh_user_token(
+    db: web::Data<MySqlPool>,
+    keys: web::Data<JwtKeys>,
+    info: web::Json<RefreshRequest>,
+) -> impl Responder {
+    refresh_token_handler(db, keys, info).await
+}
+
+// 🔹 LOGIC HANDLER for logout — revokes the session the refresh token belongs to
+pub async fn logout_user_handler(
+    db: web::Data<MySqlPool>,
+    info: web::Json<LogoutRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let stored = find_refresh_token(db.get_ref(), &info.refresh_token)
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("Invalid refresh token"))?;
+
+    revoke_token_family(db.get_ref(), &stored.family_id).await?;
+    Ok(HttpResponse::Ok().body("Logged out"))
+}
+
+// 🔹 ROUTE HANDLER for logout
+#[post("/logout")]
+pub async fn logout_user(
+    db: web::Data<MySqlPool>,
+    info: web::Json<LogoutRequest>,
+) -> impl Responder {
+    logout_user_handler(db, info).await
+}
+
+// 🔹 LOGIC HANDLER for forgot-password — mails a single-use reset link. Like
+// resend-verification, the answer never reveals whether the address exists.
+pub async fn forgot_password_handler(
+    db: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
+    info: web::Json<ForgotPasswordRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let email = normalize_email(&info.email);
+    let response = HttpResponse::Accepted()
+        .body("If the address belongs to an account, a reset link is on its way");
+
+    let user_id = sqlx::query_scalar!(
+        "SELECT id FROM users WHERE email = ? AND deleted_at IS NULL",
+        email
+    )
+    .fetch_optional(db.get_ref())
+    .await?;
+
+    let Some(user_id) = user_id else {
+        return Ok(response);
+    };
+
+    if token_recently_sent(db.get_ref(), user_id, TokenPurpose::PasswordReset).await? {
+        return Ok(response);
+    }
+
+    // Only the newest reset link stays valid
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        "UPDATE user_tokens SET used_at = ? WHERE user_id = ? AND purpose = ? AND used_at IS NULL",
+        Utc::now().naive_utc(),
+        user_id,
+        TokenPurpose::PasswordReset.as_str()
+    )
+    .execute(&mut *tx)
+    .await?;
+    let token = issue_user_token(&mut *tx, user_id, TokenPurpose::PasswordReset, password_reset_ttl()).await?;
+    tx.commit().await?;
+
+    let message = Email {
+        to: email,
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Choose a new password by opening the link below:\n\n{}/reset-password?token={}\n\n\
+             The link expires in {} minutes. If you did not ask for a reset, ignore this email.",
+            app_base_url(),
+            token,
+            password_reset_ttl().num_minutes()
+        ),
+    };
+
+    if let Err(e) = mailer.send(&message) {
+        eprintln!("Error sending password reset email to user {}: {}", user_id, e);
+    }
+
+    Ok(response)
+}
+
+// 🔹 ROUTE HANDLER for forgot-password
+#[post("/forgot-password")]
+pub async fn forgot_password(
+    db: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
+    info: web::Json<ForgotPasswordRequest>,
+) -> impl Responder {
+    forgot_password_handler(db, mailer, info).await
+}
+
+/// Check a new password against the configured policy, reporting it under `field`
+pub fn validate_new_password(field: &str, password: &str) -> Result<(), ApiError> {
+    let mut errors = FieldErrors::default();
+    for problem in PasswordPolicy::from_env().violations(password) {
+        errors.add(field, problem);
+    }
+    errors.into_result("Invalid password")
+}
+
+// 🔹 LOGIC HANDLER for reset-password — redeems the token, sets the new
+// password and signs the account out everywhere
+pub async fn reset_password_handler(
+    db: web::Data<MySqlPool>,
+    info: web::Json<ResetPasswordRequest>,
+) -> Result<HttpResponse, ApiError> {
+    validate_new_password("new_password", &info.new_password)?;
+    let hash = hash_password(&info.new_password)?;
+    let now = Utc::now().naive_utc();
+
+    let mut tx = db.begin().await?;
+
+    let user_id = consume_user_token(&mut tx, &info.token, TokenPurpose::PasswordReset)
+        .await?
+        .ok_or_else(|| ApiError::bad_request("Invalid or expired reset token"))?;
+
+    // Receiving the reset mail proves ownership of the address as well, and
+    // lifts any failed-login lockout
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET password_hash = ?, email_verified_at = COALESCE(email_verified_at, ?),
+            failed_login_count = 0, locked_until = NULL
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        hash,
+        now,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    revoke_user_sessions(&mut *tx, user_id).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().body("Password has been reset; please log in again"))
+}
+
+// 🔹 ROUTE HANDLER for reset-password
+#[post("/reset-password")]
+pub async fn reset_password(
+    db: web::Data<MySqlPool>,
+    info: web::Json<ResetPasswordRequest>,
+) -> impl Responder {
+    reset_password_handler(db, info).await
 }
@@ -1,71 +1,666 @@
-use crate::middleware::auth_middleware::Claims;
-use crate::models::company::{Company, CreateCompany};
-use actix_web::{web, HttpRequest, HttpResponse, Responder, HttpMessage};
-use sqlx::MySqlPool;
-use chrono::Utc;
+use crate::middleware::extractors::{
+    AuthUser, RequireRole, Employer, RequireCompanyRole, CompanyViewer, CompanyOwner,
+};
+use crate::models::company::{
+    Company, CompanyProfile, CompanyMediaKind, CompanyMediaView, MediaVariant, CreateCompany, UpdateCompany,
+    DeleteCompanyQuery, CompanyRole, CompanyMember, AddCompanyMember,
+};
+use crate::errors::ApiError;
+use crate::middleware::auth_middleware::{ensure_email_verified, has_company_role, Claims};
+use crate::utils::images;
+use crate::utils::storage::MediaStorage;
+use crate::utils::tokens::generate_token;
+use crate::utils::validation::{clean, is_valid_url, merge, FieldErrors};
+use actix_multipart::{Multipart, MultipartError};
+use actix_web::{web, HttpResponse};
+use futures_util::TryStreamExt;
+use sqlx::{MySqlConnection, MySqlPool};
+use std::env;
+use chrono::{Datelike, Utc};
+use serde_json::json;
+
+/// Columns selected into `Company`
+const COMPANY_COLUMNS: &str = "id, name, description, location, website, industry, size_range, founded_year, \
+     linkedin_url, twitter_url, facebook_url, logo_url, cover_image_url, user_id, created_at, updated_at";
+
+/// Collect every problem with a company profile about to be saved
+fn validate_company(company: &Company) -> Result<(), ApiError> {
+    let mut errors = FieldErrors::default();
+
+    if company.name.is_empty() || company.name.chars().count() > 255 {
+        errors.add("name", "must be between 1 and 255 characters");
+    }
+    if company.location.as_ref().is_some_and(|v| v.chars().count() > 255) {
+        errors.add("location", "must be at most 255 characters");
+    }
+    if company.industry.as_ref().is_some_and(|v| v.chars().count() > 100) {
+        errors.add("industry", "must be at most 100 characters");
+    }
+    let current_year = Utc::now().year() as i16;
+    if company.founded_year.is_some_and(|year| !(1800..=current_year).contains(&year)) {
+        errors.add("founded_year", format!("must be between 1800 and {}", current_year));
+    }
+
+    let urls = [
+        ("website", &company.website),
+        ("linkedin_url", &company.linkedin_url),
+        ("twitter_url", &company.twitter_url),
+        ("facebook_url", &company.facebook_url),
+    ];
+    for (field, url) in urls {
+        if url.as_deref().is_some_and(|url| !is_valid_url(url)) {
+            errors.add(field, "must be an http(s) URL");
+        }
+    }
+
+    errors.into_result("Invalid company details")
+}
 
 // ✅ Create Company with user_id from token claims
 pub async fn create_company(
-    req: HttpRequest,
+    employer: RequireRole<Employer>,
     db: web::Data<MySqlPool>,
     info: web::Json<CreateCompany>,
-) -> impl Responder {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return HttpResponse::Unauthorized().body("Unauthorized"),
+) -> Result<HttpResponse, ApiError> {
+    let user_id = employer.sub;
+    ensure_email_verified(db.get_ref(), &employer).await?;
+
+    let now = Utc::now().naive_utc();
+    let info = info.into_inner();
+    let company = Company {
+        id: 0,
+        name: info.name.trim().to_string(),
+        description: clean(info.description),
+        location: clean(info.location),
+        website: clean(info.website),
+        industry: clean(info.industry),
+        size_range: info.size_range.map(|size| size.as_str().to_string()),
+        founded_year: info.founded_year,
+        linkedin_url: clean(info.linkedin_url),
+        twitter_url: clean(info.twitter_url),
+        facebook_url: clean(info.facebook_url),
+        logo_url: None,
+        cover_image_url: None,
+        user_id: Some(user_id),
+        created_at: Some(now),
+        updated_at: Some(now),
     };
+    validate_company(&company)?;
 
-    let user_id = &claims.sub;
+    let mut tx = db.begin().await?;
 
     let result = sqlx::query!(
         r#"
-        INSERT INTO companies (name, description, user_id, created_at)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO companies (
+            name, description, location, website, industry, size_range, founded_year,
+            linkedin_url, twitter_url, facebook_url, user_id, created_at, updated_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
-        info.name,
-        info.description,
+        company.name,
+        company.description,
+        company.location,
+        company.website,
+        company.industry,
+        company.size_range,
+        company.founded_year,
+        company.linkedin_url,
+        company.twitter_url,
+        company.facebook_url,
         user_id,
-        Utc::now().naive_utc()
+        now,
+        now
     )
-    .execute(db.get_ref())
-    .await;
+    .execute(&mut *tx)
+    .await?;
+    let company_id = result.last_insert_id();
 
-    match result {
-        Ok(_) => HttpResponse::Created().body("Company created"),
-        Err(e) => {
-            eprintln!("DB Error: {:?}", e);
-            HttpResponse::InternalServerError().body("Failed to create company")
-        }
-    }
+    // The creator becomes the company's first owner
+    sqlx::query!(
+        "INSERT INTO company_members (company_id, user_id, role, created_at) VALUES (?, ?, ?, ?)",
+        company_id,
+        user_id,
+        CompanyRole::Owner.as_str(),
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(HttpResponse::Created().body("Company created"))
 }
 
 // ✅ Get all companies (you can add pagination later)
-pub async fn get_all_companies(req: HttpRequest, db: web::Data<MySqlPool>) -> impl Responder {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return HttpResponse::Unauthorized().body("Unauthorized"),
+pub async fn get_all_companies(_user: AuthUser, db: web::Data<MySqlPool>) -> Result<HttpResponse, ApiError> {
+    let companies = sqlx::query_as::<_, Company>(&format!(
+        "SELECT {} FROM companies WHERE deleted_at IS NULL ORDER BY created_at DESC",
+        COMPANY_COLUMNS
+    ))
+    .fetch_all(db.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(companies))
+}
+
+/// Fetch a live (not soft-deleted) company or 404
+async fn find_company(db: &MySqlPool, id: i32) -> Result<Company, ApiError> {
+    sqlx::query_as::<_, Company>(&format!(
+        "SELECT {} FROM companies WHERE id = ? AND deleted_at IS NULL",
+        COMPANY_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| ApiError::not_found("Company not found"))
+}
+
+/// Whether the caller may edit or delete a company: admins and its current
+/// owners. Creating a company grants nothing by itself (`companies.user_id` is
+/// only a record), so a creator removed from its members loses control.
+async fn can_manage_company(db: &MySqlPool, claims: &Claims, company: &Company) -> Result<bool, sqlx::Error> {
+    has_company_role(db, claims, company.id, CompanyRole::Owner).await
+}
+
+// ✅ Get a company page: the full profile and its open job count
+pub async fn get_company_by_id(
+    _user: AuthUser,
+    db: web::Data<MySqlPool>,
+    company_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let company = find_company(db.get_ref(), *company_id).await?;
+
+    let open_jobs = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM jobs
+        WHERE company_id = ? AND deleted_at IS NULL AND status = 'published'
+          AND (expires_at IS NULL OR expires_at > UTC_TIMESTAMP())
+        "#,
+    )
+    .bind(company.id)
+    .fetch_one(db.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(CompanyProfile { company, open_jobs }))
+}
+
+/// Update a company — allowed for admins, its creator and its owners.
+/// With `replace` (PUT) every field is overwritten; otherwise (PATCH) only the
+/// fields present in the body change.
+pub async fn update_company(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    company_id: web::Path<i32>,
+    info: web::Json<UpdateCompany>,
+    replace: bool,
+) -> Result<HttpResponse, ApiError> {
+    let existing = find_company(db.get_ref(), *company_id).await?;
+    if !can_manage_company(db.get_ref(), &user, &existing).await? {
+        return Err(ApiError::forbidden("You cannot edit this company"));
+    }
+
+    let update = info.into_inner();
+    let size_range = update.size_range.map(|size| size.as_str().to_string());
+    let updated = if replace {
+        let name = update.name.ok_or_else(|| {
+            ApiError::validation("Invalid company details", json!({ "name": ["is required"] }))
+        })?;
+
+        Company {
+            name: name.trim().to_string(),
+            description: clean(update.description),
+            location: clean(update.location),
+            website: clean(update.website),
+            industry: clean(update.industry),
+            size_range,
+            founded_year: update.founded_year,
+            linkedin_url: clean(update.linkedin_url),
+            twitter_url: clean(update.twitter_url),
+            facebook_url: clean(update.facebook_url),
+            updated_at: Some(Utc::now().naive_utc()),
+            ..existing
+        }
+    } else {
+        Company {
+            name: update.name.map(|name| name.trim().to_string()).unwrap_or(existing.name),
+            description: merge(update.description, existing.description),
+            location: merge(update.location, existing.location),
+            website: merge(update.website, existing.website),
+            industry: merge(update.industry, existing.industry),
+            size_range: size_range.or(existing.size_range),
+            founded_year: update.founded_year.or(existing.founded_year),
+            linkedin_url: merge(update.linkedin_url, existing.linkedin_url),
+            twitter_url: merge(update.twitter_url, existing.twitter_url),
+            facebook_url: merge(update.facebook_url, existing.facebook_url),
+            updated_at: Some(Utc::now().naive_utc()),
+            ..existing
+        }
     };
+    validate_company(&updated)?;
 
-    let companies = sqlx::query_as!(
-        Company,
+    sqlx::query!(
         r#"
-        SELECT
-            id,
-            name,
-            description,
-            user_id,
-            created_at
-        FROM companies
-        ORDER BY created_at DESC
+        UPDATE companies
+        SET name = ?, description = ?, location = ?, website = ?, industry = ?, size_range = ?,
+            founded_year = ?, linkedin_url = ?, twitter_url = ?, facebook_url = ?, updated_at = ?
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        updated.name,
+        updated.description,
+        updated.location,
+        updated.website,
+        updated.industry,
+        updated.size_range,
+        updated.founded_year,
+        updated.linkedin_url,
+        updated.twitter_url,
+        updated.facebook_url,
+        updated.updated_at,
+        updated.id
+    )
+    .execute(db.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+/// Soft-delete a company — allowed for admins, its creator and its owners.
+/// A company with live jobs is only deleted with `?cascade=true`, which
+/// soft-deletes those jobs as well; an admin can restore either.
+pub async fn delete_company(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    company_id: web::Path<i32>,
+    query: web::Query<DeleteCompanyQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let company = find_company(db.get_ref(), *company_id).await?;
+    if !can_manage_company(db.get_ref(), &user, &company).await? {
+        return Err(ApiError::forbidden("You cannot delete this company"));
+    }
+
+    let now = Utc::now().naive_utc();
+    let mut tx = db.begin().await?;
+
+    let live_jobs = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM jobs WHERE company_id = ? AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(company.id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if live_jobs > 0 {
+        if !query.cascade {
+            return Err(ApiError::conflict(format!(
+                "Company still has {} job(s); delete them first or pass cascade=true",
+                live_jobs
+            )));
+        }
+        sqlx::query!(
+            "UPDATE jobs SET deleted_at = ? WHERE company_id = ? AND deleted_at IS NULL",
+            now,
+            company.id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query!(
+        "UPDATE companies SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+        now,
+        company.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().body("Company deleted"))
+}
+
+// ✅ List members of a company (any member or admin)
+pub async fn get_company_members(
+    member: RequireCompanyRole<CompanyViewer>,
+    db: web::Data<MySqlPool>,
+) -> Result<HttpResponse, ApiError> {
+    let members = sqlx::query_as::<_, CompanyMember>(
+        r#"
+        SELECT m.company_id, m.user_id, u.email, m.role, m.created_at
+        FROM company_members m
+        JOIN users u ON m.user_id = u.id
+        WHERE m.company_id = ? AND u.deleted_at IS NULL
+        ORDER BY m.created_at ASC
         "#
     )
+    .bind(member.company_id)
     .fetch_all(db.get_ref())
-    .await;
+    .await?;
+
+    Ok(HttpResponse::Ok().json(members))
+}
+
+/// Refuse a change that would take ownership from `user_id` while nobody else
+/// owns the company. Locks the company's owner rows, so concurrent changes
+/// are checked one after another; call it inside the changing transaction.
+async fn ensure_another_owner(conn: &mut MySqlConnection, company_id: i32, user_id: i32) -> Result<(), ApiError> {
+    let owners = sqlx::query_scalar::<_, i32>(
+        "SELECT user_id FROM company_members WHERE company_id = ? AND role = 'owner' FOR UPDATE",
+    )
+    .bind(company_id)
+    .fetch_all(conn)
+    .await?;
+
+    if owners.contains(&user_id) && owners.iter().all(|&owner| owner == user_id) {
+        return Err(ApiError::conflict("A company must keep at least one owner"));
+    }
+    Ok(())
+}
+
+// ✅ Add a member or change their role (owners and admins only); the last
+// owner cannot be demoted
+pub async fn add_company_member(
+    owner: RequireCompanyRole<CompanyOwner>,
+    db: web::Data<MySqlPool>,
+    info: web::Json<AddCompanyMember>,
+) -> Result<HttpResponse, ApiError> {
+    let mut tx = db.begin().await?;
 
-    match companies {
-        Ok(data) => HttpResponse::Ok().json(data),
-        Err(_) => HttpResponse::InternalServerError().body("Failed to fetch companies"),
+    if info.role != CompanyRole::Owner {
+        ensure_another_owner(&mut tx, owner.company_id, info.user_id).await?;
     }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO company_members (company_id, user_id, role, created_at)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE role = VALUES(role)
+        "#,
+        owner.company_id,
+        info.user_id,
+        info.role.as_str(),
+        Utc::now().naive_utc()
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().body("Member saved"))
+}
+
+// ✅ Remove a member (owners and admins only); the last owner cannot be removed
+pub async fn remove_company_member(
+    owner: RequireCompanyRole<CompanyOwner>,
+    db: web::Data<MySqlPool>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let company_id = owner.company_id;
+    let (_, member_id) = path.into_inner();
+
+    let mut tx = db.begin().await?;
+    ensure_another_owner(&mut tx, company_id, member_id).await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM company_members WHERE company_id = ? AND user_id = ?",
+        company_id,
+        member_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Member not found"));
+    }
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().body("Member removed"))
+}
+
+/// Largest accepted upload (MEDIA_MAX_UPLOAD_BYTES, default 5 MiB)
+fn max_upload_bytes() -> usize {
+    env::var("MEDIA_MAX_UPLOAD_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5 * 1024 * 1024)
+}
+
+fn multipart_error(e: MultipartError) -> ApiError {
+    ApiError::bad_request(format!("Invalid multipart body: {}", e))
+}
+
+/// Read the `file` part of a multipart body, returning its declared content
+/// type and bytes. Other parts are skipped.
+async fn read_upload(mut payload: Multipart) -> Result<(String, Vec<u8>), ApiError> {
+    let limit = max_upload_bytes();
+
+    while let Some(mut field) = payload.try_next().await.map_err(multipart_error)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let content_type = field
+            .content_type()
+            .map(|mime| mime.essence_str().to_string())
+            .unwrap_or_default();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(multipart_error)? {
+            if bytes.len() + chunk.len() > limit {
+                return Err(ApiError::payload_too_large(format!(
+                    "Images may be at most {} bytes",
+                    limit
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        return Ok((content_type, bytes));
+    }
+
+    Err(ApiError::validation("Invalid image", json!({ "file": ["is required"] })))
+}
+
+/// Best-effort removal of stored objects; failures are only logged
+async fn delete_objects(storage: &dyn MediaStorage, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = storage.delete(key).await {
+            eprintln!("Error deleting media {}: {}", key, e);
+        }
+    }
+}
+
+/// Point the company's profile at its current logo or cover image
+async fn set_media_url(
+    conn: &mut MySqlConnection,
+    company_id: i32,
+    kind: CompanyMediaKind,
+    url: Option<&str>,
+) -> Result<u64, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let result = match kind {
+        CompanyMediaKind::Logo => {
+            sqlx::query!("UPDATE companies SET logo_url = ?, updated_at = ? WHERE id = ?", url, now, company_id)
+                .execute(conn)
+                .await?
+        }
+        CompanyMediaKind::Cover => {
+            sqlx::query!(
+                "UPDATE companies SET cover_image_url = ?, updated_at = ? WHERE id = ?",
+                url,
+                now,
+                company_id
+            )
+            .execute(conn)
+            .await?
+        }
+    };
+    Ok(result.rows_affected())
+}
+
+/// Swap the stored variants of one kind for new ones; returns the keys of the
+/// variants replaced
+async fn replace_media_rows(
+    db: &MySqlPool,
+    company_id: i32,
+    kind: CompanyMediaKind,
+    variants: &[(String, MediaVariant, usize)],
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let previous = sqlx::query_scalar!(
+        "SELECT storage_key FROM company_media WHERE company_id = ? AND kind = ? FOR UPDATE",
+        company_id,
+        kind.as_str()
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM company_media WHERE company_id = ? AND kind = ?",
+        company_id,
+        kind.as_str()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let now = Utc::now().naive_utc();
+    for (key, variant, byte_size) in variants {
+        sqlx::query!(
+            r#"
+            INSERT INTO company_media
+                (company_id, kind, variant, storage_key, content_type, width, height, byte_size, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            company_id,
+            kind.as_str(),
+            variant.variant,
+            key,
+            variant.content_type,
+            variant.width,
+            variant.height,
+            *byte_size as i32,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let url = variants.first().map(|(_, variant, _)| variant.url.as_str());
+    set_media_url(&mut tx, company_id, kind, url).await?;
+    tx.commit().await?;
+
+    Ok(previous)
+}
+
+/// Upload a company logo or cover image (multipart, field `file`) — allowed
+/// for admins, the company's creator and its owners. The image is checked,
+/// resized into its variants and stored; any previous one is replaced.
+pub async fn upload_company_media(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    storage: web::Data<dyn MediaStorage>,
+    company_id: web::Path<i32>,
+    payload: Multipart,
+    kind: CompanyMediaKind,
+) -> Result<HttpResponse, ApiError> {
+    let company = find_company(db.get_ref(), *company_id).await?;
+    if !can_manage_company(db.get_ref(), &user, &company).await? {
+        return Err(ApiError::forbidden("You cannot edit this company"));
+    }
+
+    let (content_type, bytes) = read_upload(payload).await?;
+    let spec = match kind {
+        CompanyMediaKind::Logo => &images::LOGO,
+        CompanyMediaKind::Cover => &images::COVER,
+    };
+    let variants = web::block(move || images::process_image(&bytes, &content_type, spec))
+        .await
+        .map_err(|e| {
+            eprintln!("Image processing task failed: {:?}", e);
+            ApiError::Internal
+        })??;
+
+    // A fresh prefix per upload, so cached URLs of the old image never serve the new one
+    let version = &generate_token()[..8];
+    let mut stored: Vec<(String, MediaVariant, usize)> = Vec::with_capacity(variants.len());
+    for image in variants {
+        let key = format!(
+            "companies/{}/{}/{}-{}.{}",
+            company.id,
+            kind.as_str(),
+            version,
+            image.name,
+            image.extension
+        );
+        let byte_size = image.bytes.len();
+        if let Err(e) = storage.put(&key, image.bytes, image.content_type).await {
+            eprintln!("Error storing media {}: {}", key, e);
+            let keys: Vec<String> = stored.into_iter().map(|(key, _, _)| key).collect();
+            delete_objects(storage.get_ref(), &keys).await;
+            return Err(ApiError::Internal);
+        }
+
+        let variant = MediaVariant {
+            variant: image.name.to_string(),
+            url: storage.url(&key),
+            content_type: image.content_type.to_string(),
+            width: image.width as i32,
+            height: image.height as i32,
+        };
+        stored.push((key, variant, byte_size));
+    }
+
+    let previous = match replace_media_rows(db.get_ref(), company.id, kind, &stored).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            let keys: Vec<String> = stored.into_iter().map(|(key, _, _)| key).collect();
+            delete_objects(storage.get_ref(), &keys).await;
+            return Err(e.into());
+        }
+    };
+    delete_objects(storage.get_ref(), &previous).await;
+
+    let variants: Vec<MediaVariant> = stored.into_iter().map(|(_, variant, _)| variant).collect();
+    Ok(HttpResponse::Created().json(CompanyMediaView {
+        kind,
+        url: variants[0].url.clone(),
+        variants,
+    }))
+}
+
+/// Remove a company's logo or cover image — allowed for admins, the company's
+/// creator and its owners
+pub async fn delete_company_media(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    storage: web::Data<dyn MediaStorage>,
+    company_id: web::Path<i32>,
+    kind: CompanyMediaKind,
+) -> Result<HttpResponse, ApiError> {
+    let company = find_company(db.get_ref(), *company_id).await?;
+    if !can_manage_company(db.get_ref(), &user, &company).await? {
+        return Err(ApiError::forbidden("You cannot edit this company"));
+    }
+    let current_url = match kind {
+        CompanyMediaKind::Logo => &company.logo_url,
+        CompanyMediaKind::Cover => &company.cover_image_url,
+    };
+
+    let mut tx = db.begin().await?;
+    let keys = sqlx::query_scalar!(
+        "SELECT storage_key FROM company_media WHERE company_id = ? AND kind = ? FOR UPDATE",
+        company.id,
+        kind.as_str()
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    if keys.is_empty() && current_url.is_none() {
+        return Err(ApiError::not_found(format!("Company has no {}", kind.as_str())));
+    }
+
+    sqlx::query!(
+        "DELETE FROM company_media WHERE company_id = ? AND kind = ?",
+        company.id,
+        kind.as_str()
+    )
+    .execute(&mut *tx)
+    .await?;
+    set_media_url(&mut tx, company.id, kind, None).await?;
+    tx.commit().await?;
+
+    delete_objects(storage.get_ref(), &keys).await;
+    Ok(HttpResponse::Ok().body("Image removed"))
 }
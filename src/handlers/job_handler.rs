@@ -1,34 +1,63 @@
-use crate::middleware::auth_middleware::Claims;
-use crate::models::job::{CreateJob, Job, JobQueryParams};
-use actix_web::{web, put, post, HttpRequest, HttpResponse, Responder, Error};
+use crate::middleware::auth_middleware::{is_admin, has_company_role, has_job_access};
+use crate::middleware::extractors::{AuthUser, RequireRole, Employer};
+use crate::models::company::CompanyRole;
+use crate::models::job::{CreateJob, Job, JobQueryParams, UpdateJob, PublicJob, JobStatus, PublishJob};
+use crate::errors::ApiError;
+use crate::utils::validation::{clean, merge};
+use actix_web::{web, put, HttpResponse};
 use sqlx::MySqlPool;
-use actix_web::HttpMessage;
-use chrono::Utc;
-use actix_web::web::{Path, Json};
+use chrono::{NaiveDateTime, Utc};
+use serde_json::error::Category;
 use serde_json::json;
 
 
+/// Largest page size the public listing will serve
+const MAX_PUBLIC_PAGE_SIZE: u32 = 50;
+
+/// Predicate (over `jobs j`) selecting jobs anonymous visitors may see
+const PUBLIC_JOB_FILTER: &str = "j.deleted_at IS NULL AND (c.id IS NULL OR c.deleted_at IS NULL) \
+     AND j.status = 'published' AND (j.expires_at IS NULL OR j.expires_at > UTC_TIMESTAMP())";
+
+/// A job's effective status: published or paused jobs past their deadline read as expired
+const EFFECTIVE_STATUS_SQL: &str = "CASE WHEN j.status IN ('published', 'paused') \
+     AND j.expires_at IS NOT NULL AND j.expires_at <= UTC_TIMESTAMP() THEN 'expired' ELSE j.status END";
+
+/// FROM/WHERE over `jobs j` keeping live jobs only: soft-deleted jobs, and jobs
+/// of soft-deleted companies, are excluded. Callers append conditions with `AND`.
+pub const LIVE_JOBS_SQL: &str = "FROM jobs j LEFT JOIN companies c ON j.company_id = c.id \
+     WHERE j.deleted_at IS NULL AND (c.id IS NULL OR c.deleted_at IS NULL)";
+
+/// SELECT ... FROM jobs j producing `Job` rows for live jobs (see
+/// [`LIVE_JOBS_SQL`]). Callers append further conditions with `AND`.
+fn job_select() -> String {
+    format!(
+        "SELECT j.id, j.title, j.description, j.location, j.company_id, j.user_id, j.job_type, j.tags, \
+         j.views, j.created_at, j.updated_at, j.expires_at, {} AS status {}",
+        EFFECTIVE_STATUS_SQL, LIVE_JOBS_SQL
+    )
+}
+
 /// Create a new job — only allowed for authenticated users
 pub async fn create_job(
-    req: HttpRequest,
+    employer: RequireRole<Employer>,
     db: web::Data<MySqlPool>,
     info: web::Json<CreateJob>,
-) -> Result<HttpResponse, Error> {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => {
-            return Ok(HttpResponse::Unauthorized().finish());
+) -> Result<HttpResponse, ApiError> {
+    // Posting under a company requires recruiter (or owner) membership
+    if let Some(company_id) = info.company_id {
+        if !has_company_role(db.as_ref(), &employer, company_id, CompanyRole::Recruiter).await? {
+            return Err(ApiError::forbidden("Not a recruiter for this company"));
         }
-    };
-    let user_id = &claims.sub;
+    }
+
+    let user_id = &employer.sub;
     let job_type = info.job_type.clone().unwrap_or("full-time".into());
     let tags = info.tags.clone().unwrap_or("".into());
 
     let result = sqlx::query!(
         r#"
-        INSERT INTO jobs (title, description, location, company_id, user_id, job_type, tags, created_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO jobs (title, description, location, company_id, user_id, job_type, tags, status, expires_at, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         info.title,
         info.description,
@@ -37,34 +66,26 @@ pub async fn create_job(
         user_id,
         job_type,
         tags,
+        JobStatus::Draft.as_str(), // New jobs stay private until published
+        info.expires_at,
         Utc::now().naive_utc()  // Convert to NaiveDateTime here
     )
     .execute(db.as_ref())
-    .await;
+    .await?;
 
-    match result {
-        Ok(res) => Ok(HttpResponse::Created().json(format!("Job created with ID: {}", res.last_insert_id()))),
-        Err(e) => {
-            eprintln!("Error inserting job: {}", e);
-            Ok(HttpResponse::InternalServerError().body(format!("Failed to create job: {}", e)))
-        }
-    }
+    Ok(HttpResponse::Created().json(json!({
+        "id": result.last_insert_id(),
+        "message": "Job created",
+    })))
 }
 
 /// Retrieve all jobs — supports filtering & pagination
 pub async fn get_all_jobs(
-    req: HttpRequest,
+    user: AuthUser,
     db: web::Data<MySqlPool>,
     query: web::Query<JobQueryParams>,
-) -> Result<HttpResponse, Error> {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return Ok(HttpResponse::Unauthorized().body("Unauthorized: No valid token")),
-    };
-
-    let user_id = &claims.sub;
-    let role = &claims.role;
+) -> Result<HttpResponse, ApiError> {
+    let user_id = &user.sub;
 
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(10);
@@ -76,40 +97,49 @@ pub async fn get_all_jobs(
     let tags = query.tags.clone().unwrap_or_default();
     let company_id = query.company_id;
 
-    let mut sql = String::from(
-        "SELECT id, title, description, location, company_id, user_id, job_type, tags, views, created_at FROM jobs WHERE 1=1",
-    );
+    let mut sql = job_select();
     let mut args: Vec<String> = vec![];
 
-    // ⛔ Restrict regular users to their own jobs
-    if role != "admin" {
-        sql += " AND user_id = ?";
+    // ⛔ Restrict non-admins to jobs they posted or their companies' jobs
+    if !is_admin(&user) {
+        sql += " AND (j.user_id = ? OR j.company_id IN (SELECT company_id FROM company_members WHERE user_id = ?))";
+        args.push(user_id.to_string());
         args.push(user_id.to_string());
     }
 
     if !search.is_empty() {
-        sql += " AND (title LIKE ? OR description LIKE ?)";
+        sql += " AND (j.title LIKE ? OR j.description LIKE ?)";
         let like = format!("%{}%", search);
         args.push(like.clone());
         args.push(like);
     }
 
     if !location.is_empty() {
-        sql += " AND location LIKE ?";
+        sql += " AND j.location LIKE ?";
         args.push(format!("%{}%", location));
     }
 
     if !job_type.is_empty() {
-        sql += " AND job_type LIKE ?";
+        sql += " AND j.job_type LIKE ?";
         args.push(format!("%{}%", job_type));
     }
 
+    if !tags.is_empty() {
+        sql += " AND j.tags LIKE ?";
+        args.push(format!("%{}%", tags));
+    }
+
     if let Some(cid) = company_id {
-        sql += " AND company_id = ?";
+        sql += " AND j.company_id = ?";
         args.push(cid.to_string());
     }
 
-    sql += " ORDER BY created_at DESC LIMIT ? OFFSET ?";
+    if let Some(status) = query.status.as_deref().filter(|s| !s.is_empty()) {
+        sql += &format!(" AND {} = ?", EFFECTIVE_STATUS_SQL);
+        args.push(status.to_string());
+    }
+
+    sql += " ORDER BY j.created_at DESC LIMIT ? OFFSET ?";
 
     let mut query_builder = sqlx::query_as::<_, Job>(&sql);
     for val in args {
@@ -117,104 +147,351 @@ pub async fn get_all_jobs(
     }
     query_builder = query_builder.bind(limit).bind(offset);
 
-    match query_builder.fetch_all(db.as_ref()).await {
-        Ok(jobs) => Ok(HttpResponse::Ok().json(jobs)),
-        Err(e) => {
-            eprintln!("Error fetching jobs: {}", e);
-            Ok(HttpResponse::InternalServerError().body("Failed to fetch jobs"))
-        }
-    }
+    let jobs = query_builder.fetch_all(db.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(jobs))
 }
 
-/// Delete a job — only allowed for admin users
+/// Soft-delete a job — allowed for admins, the job's creator and company recruiters.
+/// The row (and its applications) stays until an admin purges it.
 pub async fn delete_job(
-    req: HttpRequest,
+    user: AuthUser,
     db: web::Data<MySqlPool>,
     job_id: web::Path<i32>,
-) -> Result<HttpResponse, Error> {
-    // Fix: Store extensions in a variable so the reference lives long enough
-    let extensions = req.extensions();
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => return Ok(HttpResponse::Unauthorized().body("Unauthorized: No token")),
-    };
+) -> Result<HttpResponse, ApiError> {
+    let (job_user_id, job_company_id) = sqlx::query_as::<_, (Option<i32>, Option<i32>)>(
+        "SELECT user_id, company_id FROM jobs WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(*job_id)
+    .fetch_optional(db.as_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Job not found"))?;
 
-    // Check if the role is admin
-    if claims.role != "admin" {
-        return Ok(HttpResponse::Forbidden().body("Forbidden: Admins only"));
+    if !has_job_access(db.as_ref(), &user, job_user_id, job_company_id, CompanyRole::Recruiter).await? {
+        return Err(ApiError::forbidden("You cannot delete this job"));
     }
 
-    let result = sqlx::query!(
-        "DELETE FROM jobs WHERE id = ?",
+    sqlx::query!(
+        "UPDATE jobs SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+        Utc::now().naive_utc(),
         *job_id
     )
     .execute(db.as_ref())
-    .await;
+    .await?;
+
+    Ok(HttpResponse::Ok().body("Job deleted successfully"))
+}
+
+/// Fetch a live (not soft-deleted) job or 404
+async fn find_job(db: &MySqlPool, id: i32) -> Result<Job, ApiError> {
+    sqlx::query_as::<_, Job>(&format!("{} AND j.id = ?", job_select()))
+        .bind(id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Job not found"))
+}
 
-    match result {
-        Ok(_) => Ok(HttpResponse::Ok().body("Job deleted successfully")),
-        Err(e) => {
-            eprintln!("Error deleting job: {}", e);
-            Ok(HttpResponse::InternalServerError().body("Failed to delete job"))
+/// Update a job — allowed for admins, the job's creator and company recruiters.
+/// With `replace` (PUT) every field is overwritten; otherwise (PATCH) only the
+/// fields present in the body change.
+pub async fn update_job(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    job_id: web::Path<i32>,
+    info: web::Json<UpdateJob>,
+    replace: bool,
+) -> Result<HttpResponse, ApiError> {
+    let id = *job_id;
+    let existing = find_job(db.as_ref(), id).await?;
+
+    if !has_job_access(db.as_ref(), &user, existing.user_id, existing.company_id, CompanyRole::Recruiter).await? {
+        return Err(ApiError::forbidden("You cannot edit this job"));
+    }
+
+    let update = info.into_inner();
+    // Same rule as publishing: a new deadline must lie ahead
+    if update.expires_at.flatten().is_some_and(|deadline| deadline <= Utc::now().naive_utc()) {
+        return Err(ApiError::bad_request("expires_at must be in the future"));
+    }
+
+    let updated = if replace {
+        let title = update.title.ok_or_else(|| ApiError::bad_request("title is required"))?;
+
+        Job {
+            title,
+            description: clean(update.description),
+            location: clean(update.location),
+            company_id: update.company_id.flatten(),
+            job_type: Some(update.job_type.unwrap_or("full-time".into())),
+            tags: Some(update.tags.unwrap_or_default()),
+            expires_at: update.expires_at.flatten(),
+            updated_at: Some(Utc::now().naive_utc()),
+            ..existing
+        }
+    } else {
+        Job {
+            title: update.title.unwrap_or(existing.title),
+            description: merge(update.description, existing.description),
+            location: merge(update.location, existing.location),
+            company_id: update.company_id.unwrap_or(existing.company_id),
+            job_type: update.job_type.or(existing.job_type),
+            tags: update.tags.or(existing.tags),
+            expires_at: update.expires_at.unwrap_or(existing.expires_at),
+            updated_at: Some(Utc::now().naive_utc()),
+            ..existing
+        }
+    };
+
+    // Moving a job to another company requires recruiter rights there too
+    if let Some(company_id) = updated.company_id.filter(|cid| Some(*cid) != existing.company_id) {
+        if !has_company_role(db.as_ref(), &user, company_id, CompanyRole::Recruiter).await? {
+            return Err(ApiError::forbidden("Not a recruiter for this company"));
         }
     }
+
+    sqlx::query!(
+        r#"
+        UPDATE jobs
+        SET title = ?, description = ?, location = ?, company_id = ?, job_type = ?, tags = ?,
+            expires_at = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+        updated.title,
+        updated.description,
+        updated.location,
+        updated.company_id,
+        updated.job_type,
+        updated.tags,
+        updated.expires_at,
+        updated.updated_at,
+        id
+    )
+    .execute(db.as_ref())
+    .await?;
+
+    // Re-read so the effective status reflects any new deadline
+    let job = find_job(db.as_ref(), id).await?;
+    Ok(HttpResponse::Ok().json(job))
+}
+
+/// Move a job through its lifecycle (publish / pause / close). Allowed for
+/// admins, the job's creator and company recruiters.
+pub async fn transition_job(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    job_id: web::Path<i32>,
+    target: JobStatus,
+    new_expires_at: Option<NaiveDateTime>,
+) -> Result<HttpResponse, ApiError> {
+    let id = *job_id;
+    let job = find_job(db.as_ref(), id).await?;
+
+    if !has_job_access(db.as_ref(), &user, job.user_id, job.company_id, CompanyRole::Recruiter).await? {
+        return Err(ApiError::forbidden("You cannot manage this job"));
+    }
+
+    let now = Utc::now().naive_utc();
+    if new_expires_at.is_some_and(|deadline| deadline <= now) {
+        return Err(ApiError::bad_request("expires_at must be in the future"));
+    }
+
+    let current: JobStatus = job.status.parse().unwrap_or(JobStatus::Closed);
+    // A fresh deadline revives an expired job, which can then be republished
+    let current = match (current, new_expires_at) {
+        (JobStatus::Expired, Some(_)) => JobStatus::Paused,
+        (status, _) => status,
+    };
+
+    if !current.can_transition_to(target) {
+        return Err(ApiError::conflict(format!(
+            "Cannot move a {} job to {}",
+            job.status,
+            target.as_str()
+        )));
+    }
+
+    // Only from the status checked above, so concurrent transitions can't skip the rules
+    let result = sqlx::query(&format!(
+        "UPDATE jobs j SET j.status = ?, j.expires_at = ?, j.updated_at = ? \
+         WHERE j.id = ? AND j.deleted_at IS NULL AND {} = ?",
+        EFFECTIVE_STATUS_SQL
+    ))
+    .bind(target.as_str())
+    .bind(new_expires_at.or(job.expires_at))
+    .bind(now)
+    .bind(id)
+    .bind(&job.status)
+    .execute(db.as_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::conflict("The job changed while it was being updated; try again"));
+    }
+
+    Ok(HttpResponse::Ok().json(json!({ "id": id, "status": target.as_str() })))
+}
+
+/// Publish a job. The body is optional, but when present it must be a valid
+/// `PublishJob`: a malformed deadline is an error, not an absent one.
+pub async fn publish_job(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    job_id: web::Path<i32>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let expires_at = if body.iter().all(u8::is_ascii_whitespace) {
+        None
+    } else {
+        let publish: PublishJob = serde_json::from_slice(&body).map_err(|e| match e.classify() {
+            Category::Data => ApiError::validation("Invalid publish details", json!({ "expires_at": [e.to_string()] })),
+            _ => ApiError::bad_request(format!("Invalid JSON body: {}", e)),
+        })?;
+        publish.expires_at
+    };
+
+    transition_job(user, db, job_id, JobStatus::Published, expires_at).await
+}
+
+/// Public job listing — no authentication, supports filtering & pagination
+pub async fn get_public_jobs(
+    db: web::Data<MySqlPool>,
+    query: web::Query<JobQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(10).clamp(1, MAX_PUBLIC_PAGE_SIZE);
+    let offset = (page - 1) * limit;
+
+    let mut sql = format!(
+        r#"
+        SELECT j.id, j.title, j.description, j.location, j.tags, j.job_type, j.views,
+               j.company_id, c.name AS company_name, j.created_at, j.expires_at
+        FROM jobs j
+        LEFT JOIN companies c ON j.company_id = c.id
+        WHERE {}
+        "#,
+        PUBLIC_JOB_FILTER
+    );
+    let mut args: Vec<String> = vec![];
+
+    if let Some(search) = query.search.as_deref().filter(|s| !s.is_empty()) {
+        sql += " AND (j.title LIKE ? OR j.description LIKE ?)";
+        let like = format!("%{}%", search);
+        args.push(like.clone());
+        args.push(like);
+    }
+
+    if let Some(location) = query.location.as_deref().filter(|s| !s.is_empty()) {
+        sql += " AND j.location LIKE ?";
+        args.push(format!("%{}%", location));
+    }
+
+    if let Some(job_type) = query.job_type.as_deref().filter(|s| !s.is_empty()) {
+        sql += " AND j.job_type LIKE ?";
+        args.push(format!("%{}%", job_type));
+    }
+
+    if let Some(tags) = query.tags.as_deref().filter(|s| !s.is_empty()) {
+        sql += " AND j.tags LIKE ?";
+        args.push(format!("%{}%", tags));
+    }
+
+    if let Some(cid) = query.company_id {
+        sql += " AND j.company_id = ?";
+        args.push(cid.to_string());
+    }
+
+    sql += " ORDER BY j.created_at DESC LIMIT ? OFFSET ?";
+
+    let mut query_builder = sqlx::query_as::<_, PublicJob>(&sql);
+    for val in args {
+        query_builder = query_builder.bind(val);
+    }
+    query_builder = query_builder.bind(limit).bind(offset);
+
+    let jobs = query_builder.fetch_all(db.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(jobs))
+}
+
+/// Public job detail — counts a view, 404s for jobs that are not public
+pub async fn get_public_job_by_id(
+    db: web::Data<MySqlPool>,
+    job_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let id = *job_id;
+
+    let sql = format!(
+        r#"
+        SELECT j.id, j.title, j.description, j.location, j.tags, j.job_type, j.views,
+               j.company_id, c.name AS company_name, j.created_at, j.expires_at
+        FROM jobs j
+        LEFT JOIN companies c ON j.company_id = c.id
+        WHERE j.id = ? AND {}
+        "#,
+        PUBLIC_JOB_FILTER
+    );
+
+    let job = sqlx::query_as::<_, PublicJob>(&sql)
+        .bind(id)
+        .fetch_optional(db.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::not_found("Job not found"))?;
+
+    let _ = sqlx::query!("UPDATE jobs SET views = views + 1 WHERE id = ? AND deleted_at IS NULL", id)
+        .execute(db.as_ref())
+        .await;
+    Ok(HttpResponse::Ok().json(job))
 }
 
 #[put("/view/{id}")]
 pub async fn increment_job_view(
     db: web::Data<MySqlPool>,
     job_id: web::Path<i32>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, ApiError> {
     let job_id = *job_id;
 
-    let res = sqlx::query!(
-        "UPDATE jobs SET views = views + 1 WHERE id = ?",
+    sqlx::query!(
+        "UPDATE jobs SET views = views + 1 WHERE id = ? AND deleted_at IS NULL",
         job_id
     )
     .execute(db.as_ref())
-    .await;
+    .await?;
 
-    match res {
-        Ok(_) => Ok(HttpResponse::Ok().body("View counted")),
-        Err(e) => {
-            eprintln!("View update error: {}", e);
-            Ok(HttpResponse::InternalServerError().body("Failed to update view"))
-        }
-    }
+    Ok(HttpResponse::Ok().body("View counted"))
 }
 
-/// Get single job by ID and increment views
+/// Get single job by ID. Admins, the poster and company members see it in any
+/// state; everyone else only sees public jobs. Only public jobs count views.
 pub async fn get_job_by_id(
+    user: AuthUser,
     db: web::Data<MySqlPool>,
     job_id: web::Path<i32>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, ApiError> {
     let id = *job_id;
+    let mut job = find_job(db.as_ref(), id).await?;
 
-    // Increment views
-    let _ = sqlx::query!("UPDATE jobs SET views = views + 1 WHERE id = ?", id)
-        .execute(db.as_ref())
-        .await;
-
-    // Fetch job
-    let job = sqlx::query_as::<_,Job>(
-        r#"
-        SELECT id, title, description, location, company_id, user_id, created_at,
-               job_type, tags, views
-        FROM jobs WHERE id = ?
-        "#
-    )
+    let public = sqlx::query_scalar::<_, i32>(&format!(
+        "SELECT j.id FROM jobs j LEFT JOIN companies c ON j.company_id = c.id WHERE j.id = ? AND {}",
+        PUBLIC_JOB_FILTER
+    ))
     .bind(id)
     .fetch_optional(db.as_ref())
-    .await;
-
-    match job {
-        Ok(Some(job)) => Ok(HttpResponse::Ok().json(job)),
-        Ok(None) => Ok(HttpResponse::NotFound().body("Job not found")),
-        Err(e) => {
-            eprintln!("Error fetching job: {}", e);
-            Ok(HttpResponse::InternalServerError().body("Failed to fetch job"))
+    .await?
+    .is_some();
+
+    // 🔒 Drafts and paused/closed jobs look missing to outsiders
+    if !public && !has_job_access(db.as_ref(), &user, job.user_id, job.company_id, CompanyRole::Viewer).await? {
+        return Err(ApiError::not_found("Job not found"));
+    }
+
+    if public {
+        let counted = sqlx::query!("UPDATE jobs SET views = views + 1 WHERE id = ? AND deleted_at IS NULL", id)
+            .execute(db.as_ref())
+            .await;
+        if counted.is_ok() {
+            job.views = Some(job.views.unwrap_or(0) + 1);
         }
     }
+
+    Ok(HttpResponse::Ok().json(job))
 }
 
 // #[post("/jobs/{id}/apply")]
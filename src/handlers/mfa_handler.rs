@@ -0,0 +1,183 @@
+// src/handlers/mfa_handler.rs
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::MySqlPool;
+use crate::errors::ApiError;
+use crate::handlers::auth_handler::{claim_totp_step, verify_current_password};
+use crate::middleware::extractors::{AuthUser, RequireRole, Employer};
+use crate::models::session::ClientInfo;
+use crate::models::auth::{TotpSetupResponse, TotpCodeRequest, DisableTotpRequest, RecoveryCodesResponse};
+use crate::utils::tokens::hash_token;
+use crate::utils::totp::{self, generate_recovery_code, normalize_recovery_code, RECOVERY_CODE_COUNT};
+use chrono::Utc;
+use serde_json::json;
+
+fn invalid_code() -> ApiError {
+    ApiError::validation("Invalid authentication code", json!({ "code": ["is invalid"] }))
+}
+
+/// Replace the user's recovery codes with a fresh set and return them in plaintext
+async fn replace_recovery_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+    user_id: i32,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = ?", user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let now = Utc::now().naive_utc();
+    let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+    for _ in 0..RECOVERY_CODE_COUNT {
+        let code = generate_recovery_code();
+        sqlx::query!(
+            "INSERT INTO mfa_recovery_codes (user_id, code_hash, created_at) VALUES (?, ?, ?)",
+            user_id,
+            hash_token(&normalize_recovery_code(&code)),
+            now
+        )
+        .execute(&mut **tx)
+        .await?;
+        codes.push(code);
+    }
+
+    Ok(codes)
+}
+
+/// Check a TOTP code for an enrolled user and mark its time step as used
+async fn verify_enrolled_code(db: &MySqlPool, user_id: i32, code: &str) -> Result<(), ApiError> {
+    let user = sqlx::query!(
+        r#"
+        SELECT totp_secret, totp_last_step FROM users
+        WHERE id = ? AND deleted_at IS NULL AND totp_enabled_at IS NOT NULL
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| ApiError::bad_request("Two-factor authentication is not enabled"))?;
+
+    let secret = user.totp_secret.as_deref().unwrap_or_default();
+    match totp::verify_code(secret, code, user.totp_last_step)? {
+        Some(step) if claim_totp_step(db, user_id, step).await? => Ok(()),
+        _ => Err(invalid_code()),
+    }
+}
+
+/// Start TOTP enrollment (POST /users/me/mfa/totp/setup). Generates a new secret,
+/// which stays inactive until confirmed with a code from the authenticator app.
+pub async fn setup_totp(
+    user: RequireRole<Employer>,
+    db: web::Data<MySqlPool>,
+) -> Result<HttpResponse, ApiError> {
+    let account = sqlx::query!(
+        "SELECT email, totp_enabled_at FROM users WHERE id = ? AND deleted_at IS NULL",
+        user.sub
+    )
+    .fetch_optional(db.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("User not found"))?;
+
+    if account.totp_enabled_at.is_some() {
+        return Err(ApiError::conflict("Two-factor authentication is already enabled"));
+    }
+
+    let secret = totp::generate_secret();
+    let otpauth_uri = totp::provisioning_uri(&secret, &account.email)?;
+
+    sqlx::query!(
+        "UPDATE users SET totp_secret = ?, totp_last_step = NULL WHERE id = ?",
+        secret,
+        user.sub
+    )
+    .execute(db.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(TotpSetupResponse { secret, otpauth_uri }))
+}
+
+/// Finish TOTP enrollment (POST /users/me/mfa/totp/confirm) and hand out recovery codes
+pub async fn confirm_totp(
+    user: RequireRole<Employer>,
+    db: web::Data<MySqlPool>,
+    info: web::Json<TotpCodeRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let account = sqlx::query!(
+        "SELECT totp_secret, totp_enabled_at, totp_last_step FROM users WHERE id = ? AND deleted_at IS NULL",
+        user.sub
+    )
+    .fetch_optional(db.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("User not found"))?;
+
+    if account.totp_enabled_at.is_some() {
+        return Err(ApiError::conflict("Two-factor authentication is already enabled"));
+    }
+    let secret = account
+        .totp_secret
+        .ok_or_else(|| ApiError::bad_request("Start enrollment with /users/me/mfa/totp/setup first"))?;
+
+    let step = totp::verify_code(&secret, &info.code, account.totp_last_step)?.ok_or_else(invalid_code)?;
+
+    let mut tx = db.begin().await?;
+    // Guarded on the secret so a concurrent re-setup can't enable the wrong one
+    let enabled = sqlx::query!(
+        r#"
+        UPDATE users SET totp_enabled_at = ?, totp_last_step = ?
+        WHERE id = ? AND totp_secret = ? AND totp_enabled_at IS NULL
+        "#,
+        Utc::now().naive_utc(),
+        step,
+        user.sub,
+        secret
+    )
+    .execute(&mut *tx)
+    .await?;
+    if enabled.rows_affected() == 0 {
+        return Err(ApiError::conflict("Enrollment changed; start again"));
+    }
+    let recovery_codes = replace_recovery_codes(&mut tx, user.sub).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Turn TOTP off (DELETE /users/me/mfa/totp). Needs both the password and a current code.
+pub async fn disable_totp(
+    req: HttpRequest,
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    info: web::Json<DisableTotpRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let client = ClientInfo::from_request(&req);
+    verify_current_password(db.get_ref(), user.sub, &info.password, "password", &client).await?;
+    verify_enrolled_code(db.get_ref(), user.sub, &info.code).await?;
+
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        "UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL WHERE id = ?",
+        user.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = ?", user.sub)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().body("Two-factor authentication disabled"))
+}
+
+/// Issue a new set of recovery codes (POST /users/me/mfa/recovery-codes),
+/// invalidating the old ones
+pub async fn regenerate_recovery_codes(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    info: web::Json<TotpCodeRequest>,
+) -> Result<HttpResponse, ApiError> {
+    verify_enrolled_code(db.get_ref(), user.sub, &info.code).await?;
+
+    let mut tx = db.begin().await?;
+    let recovery_codes = replace_recovery_codes(&mut tx, user.sub).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }))
+}
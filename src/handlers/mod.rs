@@ -3,4 +3,6 @@ pub mod job_handler;
 pub mod company_handler;
 pub mod user_handler;
 pub mod application_handler;
-pub mod admin_handler;
\ No newline at end of file
+pub mod admin_handler;pub mod mfa_handler;
+pub mod api_key_handler;
+pub mod oidc_handler;
@@ -1,76 +1,154 @@
 // src/handlers/user_handler.rs
-use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse};
 use sqlx::MySqlPool;
-use crate::middleware::auth_middleware::Claims;
-use crate::models::user::User;
-use actix_web::HttpMessage;
+use crate::errors::ApiError;
+use crate::middleware::extractors::{AuthUser, RequireRole, Admin};
+use crate::handlers::auth_handler::{
+    revoke_user_sessions, revoke_token_family, validate_new_password, verify_current_password,
+};
+use crate::models::auth::ChangePasswordRequest;
+use crate::models::session::{ClientInfo, SessionView};
+use crate::models::user::{User, UserProfile, AdminUserView};
+use crate::utils::passwords::hash_password;
+use chrono::Utc;
 
 /// Get current logged-in user (GET /users/me)
-pub async fn get_current_user(req: HttpRequest, db: web::Data<MySqlPool>) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        let user_id = claims.sub;
-
-        let result = sqlx::query_as!(
-            User,
-            r#"SELECT id, email, password_hash, role, created_at, updated_at
-               FROM users WHERE id = ?"#,
-            user_id
-        )
-        .fetch_one(db.get_ref())
-        .await;
-
-        match result {
-            Ok(user) => HttpResponse::Ok().json(user),
-            Err(_) => HttpResponse::NotFound().body("User not found"),
-        }
-    } else {
-        HttpResponse::Unauthorized().body("Unauthorized")
-    }
+pub async fn get_current_user(user: AuthUser, db: web::Data<MySqlPool>) -> Result<HttpResponse, ApiError> {
+    let user_id = user.sub;
+
+    let user: User = sqlx::query_as!(
+        User,
+        r#"SELECT id, email, password_hash, role, created_at, updated_at, email_verified_at, locked_until,
+                  totp_enabled_at
+           FROM users WHERE id = ? AND deleted_at IS NULL"#,
+        user_id
+    )
+    .fetch_optional(db.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("User not found"))?;
+
+    Ok(HttpResponse::Ok().json(UserProfile::from(user)))
+}
+
+/// Change the caller's password (POST /users/me/password). Requires the current
+/// password and signs every session out, this one included.
+pub async fn change_password(
+    req: HttpRequest,
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    info: web::Json<ChangePasswordRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let client = ClientInfo::from_request(&req);
+    verify_current_password(db.get_ref(), user.sub, &info.current_password, "current_password", &client).await?;
+
+    validate_new_password("new_password", &info.new_password)?;
+    let hash = hash_password(&info.new_password)?;
+
+    let mut tx = db.begin().await?;
+    sqlx::query!("UPDATE users SET password_hash = ? WHERE id = ?", hash, user.sub)
+        .execute(&mut *tx)
+        .await?;
+    revoke_user_sessions(&mut *tx, user.sub).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().body("Password changed; please log in again"))
+}
+
+/// List the caller's signed-in devices (GET /users/me/sessions). Sessions whose
+/// refresh tokens have all expired or been used up are left out.
+pub async fn get_my_sessions(user: AuthUser, db: web::Data<MySqlPool>) -> Result<HttpResponse, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT s.id, s.family_id, s.user_agent, s.ip_address, s.created_at, s.last_seen_at
+        FROM sessions s
+        WHERE s.user_id = ? AND s.revoked_at IS NULL
+          AND EXISTS (
+            SELECT 1 FROM refresh_tokens r
+            WHERE r.family_id = s.family_id AND r.revoked_at IS NULL AND r.used_at IS NULL AND r.expires_at > ?
+          )
+        ORDER BY s.last_seen_at DESC
+        "#,
+        user.sub,
+        Utc::now().naive_utc()
+    )
+    .fetch_all(db.get_ref())
+    .await?;
+
+    let sessions: Vec<SessionView> = rows
+        .into_iter()
+        .map(|row| SessionView {
+            current: row.family_id == user.sid,
+            id: row.id,
+            user_agent: row.user_agent,
+            ip_address: row.ip_address,
+            created_at: row.created_at,
+            last_seen_at: row.last_seen_at,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+/// Sign one of the caller's devices out (DELETE /users/me/sessions/{id}). Its
+/// access tokens stop working immediately and its refresh token is revoked.
+pub async fn revoke_my_session(
+    user: AuthUser,
+    db: web::Data<MySqlPool>,
+    session_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let family_id = sqlx::query_scalar!(
+        "SELECT family_id FROM sessions WHERE id = ? AND user_id = ? AND revoked_at IS NULL",
+        *session_id,
+        user.sub
+    )
+    .fetch_optional(db.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::not_found("Session not found"))?;
+
+    revoke_token_family(db.get_ref(), &family_id).await?;
+    Ok(HttpResponse::Ok().body("Session revoked"))
 }
 
 /// Get all users (admin only)
-pub async fn get_all_users(req: HttpRequest, db: web::Data<MySqlPool>) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        if claims.role != "admin" {
-            return HttpResponse::Forbidden().body("Access denied");
-        }
-
-        let result = sqlx::query_as!(
-            User,
-            r#"SELECT id, email, password_hash, role, created_at, updated_at FROM users"#
-        )
-        .fetch_all(db.get_ref())
-        .await;
-
-        match result {
-            Ok(users) => HttpResponse::Ok().json(users),
-            Err(_) => HttpResponse::InternalServerError().body("Failed to fetch users"),
-        }
-    } else {
-        HttpResponse::Unauthorized().body("Unauthorized")
-    }
+pub async fn get_all_users(_admin: RequireRole<Admin>, db: web::Data<MySqlPool>) -> Result<HttpResponse, ApiError> {
+    let users: Vec<User> = sqlx::query_as!(
+        User,
+        r#"SELECT id, email, password_hash, role, created_at, updated_at, email_verified_at, locked_until,
+                  totp_enabled_at
+           FROM users WHERE deleted_at IS NULL"#
+    )
+    .fetch_all(db.get_ref())
+    .await?;
+
+    let users: Vec<AdminUserView> = users.into_iter().map(AdminUserView::from).collect();
+    Ok(HttpResponse::Ok().json(users))
 }
 
-/// Delete a user by ID (admin only)
+/// Soft-delete a user by ID and end their sessions (admin only)
 pub async fn delete_user_by_id(
-    req: HttpRequest,
+    _admin: RequireRole<Admin>,
     db: web::Data<MySqlPool>,
     user_id: web::Path<i32>,
-) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        if claims.role != "admin" {
-            return HttpResponse::Forbidden().body("Access denied");
-        }
-
-        let result = sqlx::query!("DELETE FROM users WHERE id = ?", *user_id)
-            .execute(db.get_ref())
-            .await;
-
-        match result {
-            Ok(_) => HttpResponse::Ok().body("User deleted successfully"),
-            Err(_) => HttpResponse::InternalServerError().body("Failed to delete user"),
-        }
-    } else {
-        HttpResponse::Unauthorized().body("Unauthorized")
+) -> Result<HttpResponse, ApiError> {
+    let user_id = *user_id;
+    let now = Utc::now().naive_utc();
+
+    let mut tx = db.begin().await?;
+
+    let result = sqlx::query!(
+        "UPDATE users SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+        now,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("User not found"));
     }
+
+    revoke_user_sessions(&mut *tx, user_id).await?;
+
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().body("User deleted successfully"))
 }
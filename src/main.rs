@@ -1,17 +1,25 @@
-use actix_web::{App, HttpServer, web, HttpRequest, Responder};
-use actix_web::HttpMessage;
+use actix_files::Files;
+use actix_web::{App, HttpServer, web, Responder};
 use dotenvy::dotenv;
 use std::env;
 use sqlx::mysql::MySqlPoolOptions;
 
-use routes::{jobs, companies, users, applications, admins};
+use routes::{jobs, companies, users, applications, admins, api_keys};
 
+mod errors;
 mod handlers;
 mod routes;
 mod models;
 mod middleware;
+mod utils;
 
-use middleware::auth_middleware::{AuthMiddleware, Claims};
+use errors::ApiError;
+use middleware::auth_middleware::AuthMiddleware;
+use middleware::extractors::AuthUser;
+use utils::jwt::JwtKeys;
+use utils::mailer::mailer_from_env;
+use utils::oidc::OidcClient;
+use utils::storage::storage_from_env;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -26,12 +34,46 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to connect to the database");
 
+    let mailer = mailer_from_env();
+    let storage = storage_from_env();
+    let jwt_keys = web::Data::new(JwtKeys::from_env());
+    // Single sign-on is only offered when OIDC_ISSUER_URL is set
+    let oidc = OidcClient::from_env().map(web::Data::new);
+    // Fail fast on a bad ARGON2_* configuration rather than on the first login
+    utils::passwords::argon2_params();
+
     println!("Server running at http://{}", addr);
 
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(db_pool.clone()))
+            .app_data(web::Data::from(mailer.clone()))
+            .app_data(web::Data::from(storage.clone()))
+            .app_data(jwt_keys.clone())
+            .configure(|cfg| {
+                if let Some(oidc) = &oidc {
+                    cfg.app_data(oidc.clone());
+                }
+            })
+            // Malformed bodies, paths and query strings get the same JSON error shape
+            .app_data(web::JsonConfig::default().error_handler(|err, _| {
+                ApiError::bad_request(err.to_string()).into()
+            }))
+            .app_data(web::PathConfig::default().error_handler(|err, _| {
+                ApiError::not_found(err.to_string()).into()
+            }))
+            .app_data(web::QueryConfig::default().error_handler(|err, _| {
+                ApiError::bad_request(err.to_string()).into()
+            }))
             .configure(routes::auth::configure) // Public auth routes
+            .configure(routes::public_jobs::configure) // Public job browsing
+            .configure(routes::well_known::configure) // JWKS for token verification
+            .configure(|cfg| {
+                // Uploaded media kept on local disk is served by the app itself
+                if let Some(root) = storage.local_root() {
+                    cfg.service(Files::new("/media", root));
+                }
+            })
             .service(
                 web::scope("/api")
                     .wrap(AuthMiddleware) // 👈 Middleware applied to protected routes
@@ -40,6 +82,7 @@ async fn main() -> std::io::Result<()> {
                     .configure(users::configure)
                     .configure(applications::configure)
                     .configure(admins::configure_admin_routes)
+                    .configure(api_keys::configure)
                     .route("/dashboard", web::get().to(protected_dashboard))
             )
     })
@@ -49,13 +92,9 @@ async fn main() -> std::io::Result<()> {
 }
 
 // Example protected handler
-async fn protected_dashboard(req: HttpRequest) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        format!(
-            "Welcome user with ID: {}, role: {}",
-            claims.sub, claims.role
-        )
-    } else {
-        "Unauthorized - No token claims found".to_string()
-    }
+async fn protected_dashboard(user: AuthUser) -> impl Responder {
+    format!(
+        "Welcome user with ID: {}, role: {}",
+        user.sub, user.role
+    )
 }
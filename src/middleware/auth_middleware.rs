@@ -1,26 +1,332 @@
 use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error, HttpMessage};
 use actix_web::body::EitherBody;
 use actix_web::dev::{forward_ready, Service, Transform};
-use actix_web::http::header::AUTHORIZATION;
-use actix_web::HttpResponse;
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use actix_web::http::Method;
+use actix_web::{web, ResponseError};
 use futures_util::future::{ok, Ready, LocalBoxFuture};
-use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use chrono::{Duration, NaiveDateTime, Utc};
+use crate::models::api_key::ApiScope;
+use crate::models::company::CompanyRole;
+use crate::models::user::UserRole;
+use crate::errors::ApiError;
+use crate::utils::jwt::JwtKeys;
+use crate::utils::tokens::hash_token;
 use std::env;
-use std::future::{ready, Ready as StdReady};
-use std::pin::Pin;
 use std::rc::Rc;
-use std::task::{Context, Poll};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: i32,
-    pub role: String,
+    pub role: UserRole,
     pub exp: usize,
+    /// Issued-at; tokens dated in the future are refused
+    pub iat: usize,
+    pub iss: String,
+    pub aud: String,
+    /// Session (refresh token family) this access token was minted for
+    pub sid: String,
+    /// Whether the session was opened with a second factor
+    #[serde(default)]
+    pub mfa: bool,
+    /// Set when the caller authenticated with an API key: all it may do
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<ApiScope>>,
+    /// Set on impersonation tokens: the admin acting as `sub`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+}
+
+/// The real caller behind an impersonation token (RFC 8693 `act` claim)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Actor {
+    /// Admin doing the impersonating
+    pub sub: i32,
+    /// `impersonations` row the token belongs to
+    pub impersonation_id: i32,
+    pub read_only: bool,
 }
 
 pub fn is_admin(claims: &Claims) -> bool {
-    claims.role == "admin"
+    claims.role == UserRole::Admin
+}
+
+/// Whether admin sessions must have been opened with TOTP (REQUIRE_ADMIN_MFA,
+/// default false). Enforced in [`AuthMiddleware`], so every admin shortcut
+/// (`is_admin`, `has_company_role`, ...) is covered.
+pub fn admin_mfa_required() -> bool {
+    env::var("REQUIRE_ADMIN_MFA")
+        .map(|v| matches!(v.as_str(), "true" | "1"))
+        .unwrap_or(false)
+}
+
+/// Employers (and admins) may create companies and post jobs
+pub fn is_employer(claims: &Claims) -> bool {
+    matches!(claims.role, UserRole::Employer | UserRole::Admin)
+}
+
+/// The user's membership role in a company, if they belong to it
+pub async fn company_role(
+    db: &MySqlPool,
+    company_id: i32,
+    user_id: i32,
+) -> Result<Option<CompanyRole>, sqlx::Error> {
+    let role = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT m.role FROM company_members m
+        JOIN companies c ON m.company_id = c.id
+        WHERE m.company_id = ? AND m.user_id = ? AND c.deleted_at IS NULL
+        "#,
+    )
+    .bind(company_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(role.and_then(|r| r.parse().ok()))
+}
+
+/// Whether the caller holds at least `min` in the company (admins always do)
+pub async fn has_company_role(
+    db: &MySqlPool,
+    claims: &Claims,
+    company_id: i32,
+    min: CompanyRole,
+) -> Result<bool, sqlx::Error> {
+    if is_admin(claims) {
+        return Ok(true);
+    }
+
+    Ok(company_role(db, company_id, claims.sub)
+        .await?
+        .is_some_and(|role| role >= min))
+}
+
+/// Whether the caller may act on a job: admins, the job's creator, or members
+/// of the job's company holding at least `min`
+pub async fn has_job_access(
+    db: &MySqlPool,
+    claims: &Claims,
+    job_user_id: Option<i32>,
+    job_company_id: Option<i32>,
+    min: CompanyRole,
+) -> Result<bool, sqlx::Error> {
+    if is_admin(claims) || job_user_id == Some(claims.sub) {
+        return Ok(true);
+    }
+
+    match job_company_id {
+        Some(company_id) => has_company_role(db, claims, company_id, min).await,
+        None => Ok(false),
+    }
+}
+
+/// Whether unverified accounts are held back from applying and creating
+/// companies (REQUIRE_EMAIL_VERIFICATION, default true)
+fn email_verification_required() -> bool {
+    env::var("REQUIRE_EMAIL_VERIFICATION")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+}
+
+/// Reject callers who have not verified their email yet, when the policy is on.
+/// Admins are exempt.
+pub async fn ensure_email_verified(db: &MySqlPool, claims: &Claims) -> Result<(), ApiError> {
+    if is_admin(claims) || !email_verification_required() {
+        return Ok(());
+    }
+
+    let verified = sqlx::query_scalar::<_, Option<NaiveDateTime>>(
+        "SELECT email_verified_at FROM users WHERE id = ?",
+    )
+    .bind(claims.sub)
+    .fetch_optional(db)
+    .await?
+    .flatten()
+    .is_some();
+
+    if verified {
+        Ok(())
+    } else {
+        Err(ApiError::forbidden("Verify your email address first"))
+    }
+}
+
+/// Whether the token's session is still live, bumping its last-seen time (at
+/// most once a minute) when it is
+async fn session_is_active(db: &MySqlPool, sid: &str) -> bool {
+    let session = sqlx::query_as::<_, (i32, Option<NaiveDateTime>)>(
+        "SELECT id, last_seen_at FROM sessions WHERE family_id = ? AND revoked_at IS NULL",
+    )
+    .bind(sid)
+    .fetch_optional(db)
+    .await;
+
+    let (session_id, last_seen_at) = match session {
+        Ok(Some(session)) => session,
+        Ok(None) => return false,
+        Err(e) => {
+            eprintln!("Error checking session state: {:?}", e);
+            return false;
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+    if last_seen_at.map_or(true, |seen| seen < now - Duration::minutes(1)) {
+        let touched = sqlx::query("UPDATE sessions SET last_seen_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(session_id)
+            .execute(db)
+            .await;
+        if let Err(e) = touched {
+            eprintln!("Error recording session activity: {:?}", e);
+        }
+    }
+
+    true
+}
+
+/// Whether an impersonation is still open (not ended, not expired)
+async fn impersonation_is_active(db: &MySqlPool, impersonation_id: i32) -> bool {
+    let active = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM impersonations WHERE id = ? AND ended_at IS NULL AND expires_at > ?",
+    )
+    .bind(impersonation_id)
+    .bind(Utc::now().naive_utc())
+    .fetch_one(db)
+    .await;
+
+    match active {
+        Ok(count) => count > 0,
+        Err(e) => {
+            eprintln!("Error checking impersonation state: {:?}", e);
+            false
+        }
+    }
+}
+
+/// Append a request made under impersonation to the audit trail. Failures are
+/// logged; the response has already been produced.
+async fn log_impersonated_request(db: &MySqlPool, impersonation_id: i32, method: &Method, path: &str, status: u16) {
+    let logged = sqlx::query(
+        "INSERT INTO impersonation_requests (impersonation_id, method, path, status, requested_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(impersonation_id)
+    .bind(method.as_str())
+    .bind(path.chars().take(2048).collect::<String>())
+    .bind(status)
+    .bind(Utc::now().naive_utc())
+    .execute(db)
+    .await;
+
+    if let Err(e) = logged {
+        eprintln!("Error recording impersonated request: {:?}", e);
+    }
+}
+
+/// Headers telling clients a response was produced under impersonation
+fn mark_impersonated(headers: &mut HeaderMap, user_id: i32, actor: &Actor) {
+    headers.insert(HeaderName::from_static("x-impersonated-user"), HeaderValue::from(user_id));
+    headers.insert(HeaderName::from_static("x-impersonated-by"), HeaderValue::from(actor.sub));
+}
+
+/// Header carrying an API key; `Authorization: ApiKey <key>` works too
+const API_KEY_HEADER: &str = "X-API-Key";
+
+/// The scope an API key needs for a request, by resource and method. Anything
+/// unlisted (account, admin and key management) is closed to API keys.
+fn required_scope(method: &Method, path: &str) -> Option<ApiScope> {
+    let resource = path.strip_prefix("/api/")?.split('/').next()?;
+    let read = *method == Method::GET;
+
+    match (resource, read) {
+        ("jobs", true) => Some(ApiScope::JobsRead),
+        ("jobs", false) => Some(ApiScope::JobsWrite),
+        ("applications", true) => Some(ApiScope::ApplicationsRead),
+        ("applications", false) => Some(ApiScope::ApplicationsWrite),
+        ("companies", true) => Some(ApiScope::CompaniesRead),
+        ("companies", false) => Some(ApiScope::CompaniesWrite),
+        _ => None,
+    }
+}
+
+/// Account-management endpoints closed to impersonation tokens even when
+/// writes are allowed: a new password, second factor or API key would keep
+/// working after the impersonation ends.
+fn impersonation_forbidden(method: &Method, path: &str) -> bool {
+    is_under(path, "/api/users/me/password")
+        || is_under(path, "/api/users/me/mfa")
+        || is_under(path, "/api/api-keys")
+        || (*method == Method::DELETE && is_under(path, "/api/users/me/sessions"))
+}
+
+/// `path` is `prefix` itself or something below it
+fn is_under(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Resolve an API key to claims for its owner. Only live keys belonging to
+/// active employer accounts are accepted.
+async fn authenticate_api_key(db: &MySqlPool, keys: &JwtKeys, api_key: &str) -> Option<Claims> {
+    let now = Utc::now().naive_utc();
+    let row = sqlx::query_as::<_, (i32, i32, String, Option<NaiveDateTime>, Option<NaiveDateTime>, String)>(
+        r#"
+        SELECT k.id, k.user_id, k.scopes, k.created_at, k.expires_at, u.role
+        FROM api_keys k JOIN users u ON u.id = k.user_id
+        WHERE k.key_hash = ? AND k.revoked_at IS NULL AND (k.expires_at IS NULL OR k.expires_at > ?)
+          AND u.deleted_at IS NULL
+        "#,
+    )
+    .bind(hash_token(api_key))
+    .bind(now)
+    .fetch_optional(db)
+    .await;
+
+    let (key_id, user_id, scopes, created_at, expires_at, role) = match row {
+        Ok(row) => row?,
+        Err(e) => {
+            eprintln!("Error looking up API key: {:?}", e);
+            return None;
+        }
+    };
+
+    if role.parse::<UserRole>().ok()? != UserRole::Employer {
+        return None;
+    }
+
+    // Record usage at most once a minute per key to spare the write
+    let touched = sqlx::query(
+        "UPDATE api_keys SET last_used_at = ? WHERE id = ? AND (last_used_at IS NULL OR last_used_at < ?)",
+    )
+    .bind(now)
+    .bind(key_id)
+    .bind(now - Duration::minutes(1))
+    .execute(db)
+    .await;
+    if let Err(e) = touched {
+        eprintln!("Error recording API key use: {:?}", e);
+    }
+
+    Some(Claims {
+        sub: user_id,
+        role: UserRole::Employer,
+        exp: expires_at.map_or(usize::MAX, |at| at.and_utc().timestamp() as usize),
+        iat: created_at.map_or(0, |at| at.and_utc().timestamp() as usize),
+        iss: keys.issuer.clone(),
+        aud: keys.audience.clone(),
+        sid: format!("api_key:{}", key_id),
+        mfa: false,
+        scopes: Some(ApiScope::parse_list(&scopes)),
+        act: None,
+    })
+}
+
+fn reject<B>(req: ServiceRequest, error: ApiError) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let response = error.error_response().map_into_right_body();
+    Ok(req.into_response(response))
 }
 
 #[derive(Clone)]
@@ -60,34 +366,105 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let auth_header = req.headers().get(AUTHORIZATION).cloned();
+        let authorization = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        let api_key = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| authorization.as_deref()?.strip_prefix("ApiKey "))
+            .map(str::to_owned);
+        let bearer = authorization
+            .as_deref()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_owned);
+        let scope = required_scope(req.method(), req.path());
+        let db = req.app_data::<web::Data<MySqlPool>>().cloned();
+        let keys = req.app_data::<web::Data<JwtKeys>>().cloned();
         let srv = self.service.clone();
 
         Box::pin(async move {
-            if let Some(auth_value) = auth_header {
-                if let Ok(auth_str) = auth_value.to_str() {
-                    if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                        let secret = env::var("JWT_SECRET").unwrap();
-                        let validation = Validation::default();
-                        let decoded = decode::<Claims>(
-                            token,
-                            &DecodingKey::from_secret(secret.as_bytes()),
-                            &validation,
-                        );
-
-                        if let Ok(token_data) = decoded {
-                            req.extensions_mut().insert(token_data.claims);
-                            let res = srv.call(req).await?;
-                            return Ok(res.map_into_left_body());
-                        }
+            let (Some(db), Some(keys)) = (db, keys) else {
+                return reject(req, ApiError::unauthorized("Missing or invalid token"));
+            };
+
+            if let Some(api_key) = api_key {
+                let Some(claims) = authenticate_api_key(db.get_ref(), &keys, &api_key).await else {
+                    return reject(req, ApiError::unauthorized("Invalid API key"));
+                };
+
+                // ⛔ Keys only reach the endpoints their scopes cover
+                let granted = claims.scopes.as_deref().unwrap_or_default();
+                match scope {
+                    Some(scope) if granted.contains(&scope) => {}
+                    Some(scope) => {
+                        let message = format!("API key lacks the {} scope", scope.as_str());
+                        return reject(req, ApiError::forbidden(message));
                     }
+                    None => return reject(req, ApiError::forbidden("API keys cannot access this endpoint")),
                 }
+
+                req.extensions_mut().insert(claims);
+                let res = srv.call(req).await?;
+                return Ok(res.map_into_left_body());
+            }
+
+            if let Some(Ok(claims)) = bearer.map(|token| keys.decode(&token)) {
+                // 🕵️ Impersonation: it must still be open, account management is
+                // off limits, writes need the admin to have asked for them, and
+                // every request is logged
+                if let Some(actor) = claims.act.clone() {
+                    if !impersonation_is_active(db.get_ref(), actor.impersonation_id).await {
+                        return reject(req, ApiError::unauthorized("Impersonation has ended"));
+                    }
+
+                    let user_id = claims.sub;
+                    let method = req.method().clone();
+                    let path = req
+                        .uri()
+                        .path_and_query()
+                        .map_or_else(|| req.path().to_string(), |pq| pq.as_str().to_string());
+
+                    if impersonation_forbidden(&method, req.path()) {
+                        log_impersonated_request(db.get_ref(), actor.impersonation_id, &method, &path, 403).await;
+                        return reject(req, ApiError::forbidden("Not available while impersonating"));
+                    }
+                    if actor.read_only && !matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) {
+                        log_impersonated_request(db.get_ref(), actor.impersonation_id, &method, &path, 403).await;
+                        return reject(req, ApiError::forbidden("Impersonation is read-only"));
+                    }
+
+                    req.extensions_mut().insert(claims);
+                    let mut res = srv.call(req).await?;
+                    let status = res.status().as_u16();
+                    log_impersonated_request(db.get_ref(), actor.impersonation_id, &method, &path, status).await;
+                    mark_impersonated(res.headers_mut(), user_id, &actor);
+                    return Ok(res.map_into_left_body());
+                }
+
+                // ⛔ Reject tokens whose session was logged out or revoked
+                if !session_is_active(db.get_ref(), &claims.sid).await {
+                    return reject(req, ApiError::unauthorized("Session has been revoked"));
+                }
+
+                // ⛔ Admin sessions opened with a password alone may only enroll in TOTP
+                if is_admin(&claims)
+                    && !claims.mfa
+                    && admin_mfa_required()
+                    && !is_under(req.path(), "/api/users/me/mfa")
+                {
+                    return reject(req, ApiError::forbidden("Two-factor authentication required"));
+                }
+
+                req.extensions_mut().insert(claims);
+                let res = srv.call(req).await?;
+                return Ok(res.map_into_left_body());
             }
 
-            let response = HttpResponse::Unauthorized()
-                .body("Unauthorized: Missing or invalid token")
-                .map_into_right_body();
-            Ok(req.into_response(response))
+            reject(req, ApiError::unauthorized("Missing or invalid token"))
         })
     }
 }
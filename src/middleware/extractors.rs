@@ -0,0 +1,169 @@
+use actix_web::{dev::Payload, web, FromRequest, HttpMessage, HttpRequest};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use sqlx::MySqlPool;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use crate::errors::ApiError;
+use crate::middleware::auth_middleware::{Claims, is_admin, is_employer, has_company_role};
+use crate::models::company::CompanyRole;
+
+fn claims_from(req: &HttpRequest) -> Result<Claims, ApiError> {
+    req.extensions()
+        .get::<Claims>()
+        .cloned()
+        .ok_or_else(|| ApiError::unauthorized("Missing or invalid token"))
+}
+
+/// Any authenticated caller
+pub struct AuthUser(pub Claims);
+
+impl Deref for AuthUser {
+    type Target = Claims;
+
+    fn deref(&self) -> &Claims {
+        &self.0
+    }
+}
+
+impl FromRequest for AuthUser {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(claims_from(req).map(AuthUser))
+    }
+}
+
+/// A global role requirement usable with [`RequireRole`]
+pub trait RolePolicy {
+    const DENIED: &'static str;
+
+    fn allows(claims: &Claims) -> bool;
+}
+
+pub struct Admin;
+
+impl RolePolicy for Admin {
+    const DENIED: &'static str = "Admins only";
+
+    fn allows(claims: &Claims) -> bool {
+        is_admin(claims)
+    }
+}
+
+pub struct Employer;
+
+impl RolePolicy for Employer {
+    const DENIED: &'static str = "Employers only";
+
+    fn allows(claims: &Claims) -> bool {
+        is_employer(claims)
+    }
+}
+
+/// An authenticated caller whose global role satisfies `R`
+pub struct RequireRole<R: RolePolicy> {
+    pub claims: Claims,
+    _policy: PhantomData<R>,
+}
+
+impl<R: RolePolicy> Deref for RequireRole<R> {
+    type Target = Claims;
+
+    fn deref(&self) -> &Claims {
+        &self.claims
+    }
+}
+
+impl<R: RolePolicy> FromRequest for RequireRole<R> {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let result = claims_from(req).and_then(|claims| {
+            if R::allows(&claims) {
+                Ok(RequireRole { claims, _policy: PhantomData })
+            } else {
+                Err(ApiError::forbidden(R::DENIED))
+            }
+        });
+        ready(result)
+    }
+}
+
+/// Minimum membership role usable with [`RequireCompanyRole`]
+pub trait CompanyRolePolicy {
+    const MIN: CompanyRole;
+}
+
+pub struct CompanyViewer;
+pub struct CompanyRecruiter;
+pub struct CompanyOwner;
+
+impl CompanyRolePolicy for CompanyViewer {
+    const MIN: CompanyRole = CompanyRole::Viewer;
+}
+
+impl CompanyRolePolicy for CompanyRecruiter {
+    const MIN: CompanyRole = CompanyRole::Recruiter;
+}
+
+impl CompanyRolePolicy for CompanyOwner {
+    const MIN: CompanyRole = CompanyRole::Owner;
+}
+
+/// An authenticated caller holding at least `R` in the company named by the
+/// `{company_id}` (or `{id}`) path segment. Admins always pass. A malformed id
+/// is a 400 and, for non-members, a company that doesn't exist is a 404.
+pub struct RequireCompanyRole<R: CompanyRolePolicy> {
+    pub claims: Claims,
+    pub company_id: i32,
+    _policy: PhantomData<R>,
+}
+
+impl<R: CompanyRolePolicy> Deref for RequireCompanyRole<R> {
+    type Target = Claims;
+
+    fn deref(&self) -> &Claims {
+        &self.claims
+    }
+}
+
+impl<R: CompanyRolePolicy + 'static> FromRequest for RequireCompanyRole<R> {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let claims = claims_from(req);
+        let company_id = req
+            .match_info()
+            .get("company_id")
+            .or_else(|| req.match_info().get("id"))
+            .and_then(|id| id.parse::<i32>().ok());
+        let db = req.app_data::<web::Data<MySqlPool>>().cloned();
+
+        Box::pin(async move {
+            let claims = claims?;
+            let company_id = company_id.ok_or_else(|| ApiError::bad_request("Invalid company id"))?;
+            let db = db.ok_or(ApiError::Internal)?;
+
+            if has_company_role(db.get_ref(), &claims, company_id, R::MIN).await? {
+                return Ok(RequireCompanyRole { claims, company_id, _policy: PhantomData });
+            }
+
+            let exists = sqlx::query_scalar!(
+                "SELECT id FROM companies WHERE id = ? AND deleted_at IS NULL",
+                company_id
+            )
+            .fetch_optional(db.get_ref())
+            .await?
+            .is_some();
+            if exists {
+                Err(ApiError::forbidden("Insufficient company role"))
+            } else {
+                Err(ApiError::not_found("Company not found"))
+            }
+        })
+    }
+}
@@ -1,5 +1,4 @@
 use serde::{Deserialize, Serialize};
-use chrono::NaiveDateTime;
 
 #[derive(Serialize)]
 pub struct DashboardStats {
@@ -39,3 +38,40 @@ pub struct AdminDashboardResponse {
     pub recent_jobs: Vec<RecentJob>,
     pub recent_applications: Vec<RecentApplication>,
 }
+
+/// Soft-deletable resources addressed by the admin restore / purge routes
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashEntity {
+    Jobs,
+    Companies,
+    Users,
+}
+
+impl TrashEntity {
+    pub fn table(&self) -> &'static str {
+        match self {
+            TrashEntity::Jobs => "jobs",
+            TrashEntity::Companies => "companies",
+            TrashEntity::Users => "users",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImpersonateRequest {
+    /// Why support needs to act as this user; kept with the audit trail
+    pub reason: String,
+    /// Impersonation is read-only unless this is set
+    #[serde(default)]
+    pub allow_writes: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+    pub impersonation_id: i32,
+    pub token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub read_only: bool,
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+/// What an API key may do, stored comma-separated in `api_keys.scopes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApiScope {
+    #[serde(rename = "jobs:read")]
+    JobsRead,
+    #[serde(rename = "jobs:write")]
+    JobsWrite,
+    #[serde(rename = "applications:read")]
+    ApplicationsRead,
+    #[serde(rename = "applications:write")]
+    ApplicationsWrite,
+    #[serde(rename = "companies:read")]
+    CompaniesRead,
+    #[serde(rename = "companies:write")]
+    CompaniesWrite,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::JobsRead => "jobs:read",
+            ApiScope::JobsWrite => "jobs:write",
+            ApiScope::ApplicationsRead => "applications:read",
+            ApiScope::ApplicationsWrite => "applications:write",
+            ApiScope::CompaniesRead => "companies:read",
+            ApiScope::CompaniesWrite => "companies:write",
+        }
+    }
+
+    /// Parse the stored comma-separated list, skipping scopes this build doesn't know
+    pub fn parse_list(stored: &str) -> Vec<ApiScope> {
+        stored.split(',').filter_map(|s| s.trim().parse().ok()).collect()
+    }
+
+    pub fn join(scopes: &[ApiScope]) -> String {
+        scopes.iter().map(ApiScope::as_str).collect::<Vec<_>>().join(",")
+    }
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jobs:read" => Ok(ApiScope::JobsRead),
+            "jobs:write" => Ok(ApiScope::JobsWrite),
+            "applications:read" => Ok(ApiScope::ApplicationsRead),
+            "applications:write" => Ok(ApiScope::ApplicationsWrite),
+            "companies:read" => Ok(ApiScope::CompaniesRead),
+            "companies:write" => Ok(ApiScope::CompaniesWrite),
+            other => Err(format!("unknown scope: {}", other)),
+        }
+    }
+}
+
+/// An `api_keys` row, minus the hash
+#[derive(Debug, FromRow)]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: String,
+    pub created_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+/// An API key as shown to its owner; the secret part is never returned again
+#[derive(Debug, Serialize)]
+pub struct ApiKeyView {
+    pub id: i32,
+    pub name: String,
+    /// Leading characters of the key, to tell keys apart
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl From<ApiKey> for ApiKeyView {
+    fn from(key: ApiKey) -> Self {
+        ApiKeyView {
+            scopes: ApiScope::parse_list(&key.scopes),
+            id: key.id,
+            name: key.name,
+            prefix: key.prefix,
+            created_at: key.created_at,
+            last_used_at: key.last_used_at,
+            expires_at: key.expires_at,
+            revoked_at: key.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKey {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Omit for a key that never expires
+    pub expires_in_days: Option<i64>,
+}
+
+/// Response to key creation — the only time the full key is visible
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub details: ApiKeyView,
+}
@@ -9,6 +9,84 @@ pub struct Application {
     pub job_id: i32,
     pub resume_link: Option<String>,
     pub applied_at: NaiveDateTime,
+    pub status: String,
+}
+
+/// Hiring pipeline stage of an application
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationStatus {
+    Applied,
+    Screening,
+    Interview,
+    Offer,
+    Hired,
+    Rejected,
+    /// Set only by the applicant via the withdraw endpoint
+    Withdrawn,
+}
+
+impl ApplicationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApplicationStatus::Applied => "applied",
+            ApplicationStatus::Screening => "screening",
+            ApplicationStatus::Interview => "interview",
+            ApplicationStatus::Offer => "offer",
+            ApplicationStatus::Hired => "hired",
+            ApplicationStatus::Rejected => "rejected",
+            ApplicationStatus::Withdrawn => "withdrawn",
+        }
+    }
+
+    /// Candidates move forward one stage at a time and may be rejected at any
+    /// point before being hired; `hired`, `rejected` and `withdrawn` are final.
+    /// Withdrawal is not an employer transition, see [`Self::can_withdraw`].
+    pub fn can_transition_to(&self, next: ApplicationStatus) -> bool {
+        use ApplicationStatus::*;
+        matches!(
+            (self, next),
+            (Applied, Screening)
+                | (Screening, Interview)
+                | (Interview, Offer)
+                | (Offer, Hired)
+                | (Applied | Screening | Interview | Offer, Rejected)
+        )
+    }
+
+    /// Whether the applicant may still withdraw from this stage
+    pub fn can_withdraw(&self) -> bool {
+        use ApplicationStatus::*;
+        matches!(self, Applied | Screening | Interview | Offer)
+    }
+}
+
+impl std::str::FromStr for ApplicationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "applied" => Ok(ApplicationStatus::Applied),
+            "screening" => Ok(ApplicationStatus::Screening),
+            "interview" => Ok(ApplicationStatus::Interview),
+            "offer" => Ok(ApplicationStatus::Offer),
+            "hired" => Ok(ApplicationStatus::Hired),
+            "rejected" => Ok(ApplicationStatus::Rejected),
+            "withdrawn" => Ok(ApplicationStatus::Withdrawn),
+            other => Err(format!("unknown application status: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WithdrawApplication {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateApplicationStatus {
+    pub status: ApplicationStatus,
+    pub note: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,6 +102,7 @@ pub struct ApplicationRecord {
     pub job_id: i64,
     pub applied_at: Option<NaiveDateTime>,
     pub resume_link: Option<String>,
+    pub status: String,
     pub user_email: String,
     pub job_title: String,
 }
@@ -46,5 +125,6 @@ pub struct JobApplicationView {
     pub cover_letter: Option<String>,
     pub applied_at: Option<NaiveDateTime>,
     pub job_id: i32,
+    pub status: String,
 }
 
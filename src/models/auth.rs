@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+/// A persisted refresh token. Tokens issued from the same login share a
+/// `family_id`, which is also the `sid` claim of the access tokens they mint.
+#[derive(Debug, FromRow)]
+pub struct RefreshToken {
+    pub id: i32,
+    pub user_id: i32,
+    pub family_id: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    /// Whether the session was opened with a second factor
+    pub mfa: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogoutRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenPairResponse {
+    pub token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+/// What a single-use `user_tokens` row authorizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    EmailVerification,
+    PasswordReset,
+    /// Issued after a correct password when the account has TOTP enabled
+    MfaChallenge,
+}
+
+impl TokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::EmailVerification => "email_verification",
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::MfaChallenge => "mfa_challenge",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResendVerificationRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// First half of a two-step login: the password was right, now prove the second factor
+#[derive(Debug, Serialize)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    pub challenge_token: String,
+    pub expires_in: i64,
+}
+
+/// Second half of a two-step login: a current TOTP code or an unused recovery code
+#[derive(Debug, Deserialize)]
+pub struct MfaLoginRequest {
+    pub challenge_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpSetupResponse {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// `otpauth://` URI to render as a QR code
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisableTotpRequest {
+    pub password: String,
+    pub code: String,
+}
+
+/// Shown once; only their digests are stored
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Query string the OIDC provider redirects back with
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Set instead of `code` when the user declined or the provider failed
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
@@ -7,12 +7,180 @@ pub struct Company {
     pub id: i32,
     pub name: String,
     pub description: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub industry: Option<String>,
+    pub size_range: Option<String>,
+    pub founded_year: Option<i16>,
+    pub linkedin_url: Option<String>,
+    pub twitter_url: Option<String>,
+    pub facebook_url: Option<String>,
+    pub logo_url: Option<String>,
+    /// Set by uploading a cover image
+    pub cover_image_url: Option<String>,
     pub user_id: Option<i32>,
     pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Company page (GET /api/companies/{id}): the profile plus how many of its
+/// jobs are currently open
+#[derive(Debug, Serialize)]
+pub struct CompanyProfile {
+    #[serde(flatten)]
+    pub company: Company,
+    pub open_jobs: i64,
+}
+
+/// Headcount bracket, stored in `companies.size_range`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompanySize {
+    #[serde(rename = "1-10")]
+    Micro,
+    #[serde(rename = "11-50")]
+    Small,
+    #[serde(rename = "51-200")]
+    Medium,
+    #[serde(rename = "201-500")]
+    Large,
+    #[serde(rename = "501-1000")]
+    XLarge,
+    #[serde(rename = "1001+")]
+    Enterprise,
+}
+
+impl CompanySize {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompanySize::Micro => "1-10",
+            CompanySize::Small => "11-50",
+            CompanySize::Medium => "51-200",
+            CompanySize::Large => "201-500",
+            CompanySize::XLarge => "501-1000",
+            CompanySize::Enterprise => "1001+",
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateCompany {
     pub name: String,
     pub description: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub industry: Option<String>,
+    pub size_range: Option<CompanySize>,
+    pub founded_year: Option<i16>,
+    pub linkedin_url: Option<String>,
+    pub twitter_url: Option<String>,
+    pub facebook_url: Option<String>,
+}
+
+/// Body for PUT (full replace, `name` required) and PATCH (only the fields
+/// present are changed; an empty string clears a field) on /api/companies/{id}.
+/// The logo and cover image are managed through media uploads, not here.
+#[derive(Debug, Deserialize)]
+pub struct UpdateCompany {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub industry: Option<String>,
+    pub size_range: Option<CompanySize>,
+    pub founded_year: Option<i16>,
+    pub linkedin_url: Option<String>,
+    pub twitter_url: Option<String>,
+    pub facebook_url: Option<String>,
+}
+
+/// Query for DELETE /api/companies/{id}. Without `cascade`, a company that
+/// still has live jobs is not deleted.
+#[derive(Debug, Deserialize)]
+pub struct DeleteCompanyQuery {
+    #[serde(default)]
+    pub cascade: bool,
+}
+
+/// Which image of a company an upload is for, stored in `company_media.kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompanyMediaKind {
+    Logo,
+    Cover,
+}
+
+impl CompanyMediaKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompanyMediaKind::Logo => "logo",
+            CompanyMediaKind::Cover => "cover",
+        }
+    }
+}
+
+/// One stored size of an uploaded image
+#[derive(Debug, Serialize)]
+pub struct MediaVariant {
+    pub variant: String,
+    pub url: String,
+    pub content_type: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Result of an upload (POST /api/companies/{id}/logo or /cover). `url` is the
+/// largest variant, the one copied onto the company profile.
+#[derive(Debug, Serialize)]
+pub struct CompanyMediaView {
+    pub kind: CompanyMediaKind,
+    pub url: String,
+    pub variants: Vec<MediaVariant>,
+}
+
+/// Role of a user within a single company, stored in `company_members.role`.
+/// Variants are ordered by privilege so `role >= CompanyRole::Recruiter` works.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompanyRole {
+    Viewer,
+    Recruiter,
+    Owner,
+}
+
+impl CompanyRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompanyRole::Viewer => "viewer",
+            CompanyRole::Recruiter => "recruiter",
+            CompanyRole::Owner => "owner",
+        }
+    }
+}
+
+impl std::str::FromStr for CompanyRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(CompanyRole::Viewer),
+            "recruiter" => Ok(CompanyRole::Recruiter),
+            "owner" => Ok(CompanyRole::Owner),
+            other => Err(format!("unknown company role: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct CompanyMember {
+    pub company_id: i32,
+    pub user_id: i32,
+    pub email: String,
+    pub role: String,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddCompanyMember {
+    pub user_id: i32,
+    pub role: CompanyRole,
 }
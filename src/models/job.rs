@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use chrono::NaiveDateTime;
 use sqlx::FromRow;
 
@@ -14,6 +14,87 @@ pub struct Job {
     pub company_id: Option<i32>,
     pub user_id: Option<i32>,
     pub created_at: Option<chrono::NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    /// Effective lifecycle state (see [`JobStatus`]), `expired` included
+    pub status: String,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// Job lifecycle. `Expired` is never stored: a published or paused job whose
+/// `expires_at` has passed is reported as expired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Draft,
+    Published,
+    Paused,
+    Closed,
+    Expired,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Draft => "draft",
+            JobStatus::Published => "published",
+            JobStatus::Paused => "paused",
+            JobStatus::Closed => "closed",
+            JobStatus::Expired => "expired",
+        }
+    }
+
+    /// Whether a job in this state may be moved to `next`
+    pub fn can_transition_to(&self, next: JobStatus) -> bool {
+        use JobStatus::*;
+        matches!(
+            (self, next),
+            (Draft, Published)
+                | (Draft, Closed)
+                | (Published, Paused)
+                | (Published, Closed)
+                | (Paused, Published)
+                | (Paused, Closed)
+                | (Expired, Closed)
+        )
+    }
+}
+
+impl std::str::FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(JobStatus::Draft),
+            "published" => Ok(JobStatus::Published),
+            "paused" => Ok(JobStatus::Paused),
+            "closed" => Ok(JobStatus::Closed),
+            "expired" => Ok(JobStatus::Expired),
+            other => Err(format!("unknown job status: {}", other)),
+        }
+    }
+}
+
+/// Optional body for POST /api/jobs/{id}/publish
+#[derive(Debug, Deserialize)]
+pub struct PublishJob {
+    /// New application deadline; required to republish an expired job
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// A job as shown to anonymous visitors, with its company name embedded
+#[derive(Debug, Serialize, FromRow)]
+pub struct PublicJob {
+    pub id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub tags: Option<String>,
+    pub job_type: Option<String>,
+    pub views: Option<i32>,
+    pub company_id: Option<i32>,
+    pub company_name: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,6 +105,33 @@ pub struct CreateJob {
     pub company_id: Option<i32>,
     pub job_type: Option<String>,
     pub tags: Option<String>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// Tells an explicit `null` (`Some(None)`) apart from a missing field (`None`)
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Body for PUT (full replace, `title` required; omitted fields are cleared)
+/// and PATCH (only the fields present are changed) on /api/jobs/{id}. As with
+/// companies, an empty string clears a text field; `company_id` and
+/// `expires_at` are cleared with `null`.
+#[derive(Debug, Deserialize)]
+pub struct UpdateJob {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub company_id: Option<Option<i32>>,
+    pub job_type: Option<String>,
+    pub tags: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub expires_at: Option<Option<NaiveDateTime>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -35,4 +143,6 @@ pub struct JobQueryParams {
     pub job_type: Option<String>,
     pub tags: Option<String>,
     pub company_id: Option<i32>,
+    /// Only honoured by the authenticated listing; public jobs are always published
+    pub status: Option<String>,
 }
\ No newline at end of file
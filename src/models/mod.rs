@@ -4,3 +4,5 @@ pub mod company;
 pub mod user;
 pub mod application;
 pub mod admin;
+pub mod api_key;
+pub mod session;
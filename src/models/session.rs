@@ -0,0 +1,80 @@
+use actix_web::http::header::USER_AGENT;
+use actix_web::HttpRequest;
+use serde::Serialize;
+use chrono::NaiveDateTime;
+use std::env;
+use std::net::IpAddr;
+
+/// Reverse proxies allowed to report the client address in X-Forwarded-For
+/// (TRUSTED_PROXIES, comma-separated IPs; default none)
+fn trusted_proxies() -> Vec<IpAddr> {
+    env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect()
+}
+
+/// The connecting peer's address, or, when that peer is a trusted proxy, the
+/// nearest X-Forwarded-For hop that isn't one. Clients can put anything in
+/// the header, so entries to the left of the first untrusted hop are ignored.
+fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let trusted = trusted_proxies();
+    if !trusted.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<IpAddr> = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+
+    Some(
+        forwarded
+            .into_iter()
+            .rev()
+            .find(|hop| !trusted.contains(hop))
+            .unwrap_or(peer),
+    )
+}
+
+/// Where a request came from, as recorded on sessions and login attempts
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        ClientInfo {
+            ip: client_ip(req).map(|ip| ip.to_string()),
+            // Trimmed to fit `sessions.user_agent`
+            user_agent: req
+                .headers()
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(|ua| ua.chars().take(255).collect()),
+        }
+    }
+
+    pub fn ip(&self) -> Option<&str> {
+        self.ip.as_deref()
+    }
+}
+
+/// A signed-in device as shown to its owner (GET /api/users/me/sessions)
+#[derive(Debug, Serialize)]
+pub struct SessionView {
+    pub id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub last_seen_at: Option<NaiveDateTime>,
+    /// The session this request was made with
+    pub current: bool,
+}
@@ -2,7 +2,9 @@ use serde::{Serialize, Deserialize};
 use chrono::NaiveDateTime;
 use sqlx::FromRow;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+/// A `users` row, credentials included. Deliberately not `Serialize`: convert
+/// to [`UserProfile`] or [`AdminUserView`] before returning it to a client.
+#[derive(Debug, FromRow)]
 pub struct User {
     pub id: i32,
     pub email: String,
@@ -10,4 +12,107 @@ pub struct User {
     pub role: String,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub locked_until: Option<NaiveDateTime>,
+    pub totp_enabled_at: Option<NaiveDateTime>,
+}
+
+impl User {
+    /// Unknown roles fall back to the least privileged one
+    pub fn role(&self) -> UserRole {
+        self.role.parse().unwrap_or(UserRole::JobSeeker)
+    }
+}
+
+/// What a user sees about their own account (GET /api/users/me)
+#[derive(Debug, Serialize)]
+pub struct UserProfile {
+    pub id: i32,
+    pub email: String,
+    pub role: UserRole,
+    pub email_verified: bool,
+    pub mfa_enabled: bool,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+impl From<User> for UserProfile {
+    fn from(user: User) -> Self {
+        UserProfile {
+            role: user.role(),
+            id: user.id,
+            email: user.email,
+            email_verified: user.email_verified_at.is_some(),
+            mfa_enabled: user.totp_enabled_at.is_some(),
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// A user as listed to admins (GET /api/users)
+#[derive(Debug, Serialize)]
+pub struct AdminUserView {
+    pub id: i32,
+    pub email: String,
+    pub role: UserRole,
+    pub email_verified_at: Option<NaiveDateTime>,
+    /// Set while the account is locked out after repeated failed logins
+    pub locked_until: Option<NaiveDateTime>,
+    pub mfa_enabled: bool,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl From<User> for AdminUserView {
+    fn from(user: User) -> Self {
+        AdminUserView {
+            role: user.role(),
+            id: user.id,
+            email: user.email,
+            email_verified_at: user.email_verified_at,
+            locked_until: user.locked_until,
+            mfa_enabled: user.totp_enabled_at.is_some(),
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+/// Global account role, stored as a string in `users.role`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    // "user" is the legacy name for job seekers
+    #[serde(alias = "user")]
+    JobSeeker,
+    Employer,
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::JobSeeker => "job_seeker",
+            UserRole::Employer => "employer",
+            UserRole::Admin => "admin",
+        }
+    }
+}
+
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for UserRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "job_seeker" | "user" => Ok(UserRole::JobSeeker),
+            "employer" => Ok(UserRole::Employer),
+            "admin" => Ok(UserRole::Admin),
+            other => Err(format!("unknown role: {}", other)),
+        }
+    }
 }
@@ -1,6 +1,15 @@
 use actix_web::web;
-use crate::handlers::admin_handler::get_dashboard_stats;
+use crate::handlers::admin_handler::{
+    get_dashboard_stats, soft_delete_company, restore_deleted, purge_deleted, unlock_user, impersonate_user,
+    end_impersonation,
+};
 
 pub fn configure_admin_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(get_dashboard_stats);
+    cfg.service(soft_delete_company);
+    cfg.service(restore_deleted);
+    cfg.service(purge_deleted);
+    cfg.service(unlock_user);
+    cfg.service(impersonate_user);
+    cfg.service(end_impersonation);
 }
@@ -0,0 +1,36 @@
+use actix_web::{web, get, post, delete, Responder};
+use crate::handlers::api_key_handler::{create_api_key, list_api_keys, revoke_api_key};
+use crate::middleware::extractors::{RequireRole, Employer};
+use crate::models::api_key::CreateApiKey;
+
+#[get("")]
+async fn list_keys(user: RequireRole<Employer>, db: web::Data<sqlx::MySqlPool>) -> impl Responder {
+    list_api_keys(user, db).await
+}
+
+#[post("")]
+async fn create_key(
+    user: RequireRole<Employer>,
+    db: web::Data<sqlx::MySqlPool>,
+    body: web::Json<CreateApiKey>,
+) -> impl Responder {
+    create_api_key(user, db, body).await
+}
+
+#[delete("/{id}")]
+async fn revoke_key(
+    user: RequireRole<Employer>,
+    db: web::Data<sqlx::MySqlPool>,
+    key_id: web::Path<i32>,
+) -> impl Responder {
+    revoke_api_key(user, db, key_id).await
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api-keys")
+            .service(list_keys)
+            .service(create_key)
+            .service(revoke_key),
+    );
+}
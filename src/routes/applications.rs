@@ -1,5 +1,8 @@
 use actix_web::web;
-use crate::handlers::application_handler::{apply_to_job, list_applications, get_my_applications, get_applications_for_job};
+use crate::handlers::application_handler::{
+    apply_to_job, list_applications, get_my_applications, get_applications_for_job, update_application_status,
+    withdraw_application,
+};
 
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -7,4 +10,6 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(list_applications);
     cfg.service(get_my_applications);
     cfg.service(get_applications_for_job);
+    cfg.service(update_application_status);
+    cfg.service(withdraw_application);
 }
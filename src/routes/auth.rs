@@ -1,10 +1,23 @@
 use actix_web::web;
-use crate::handlers::auth_handler::{register_user, login_user};
+use crate::handlers::auth_handler::{
+    register_user, login_user, login_mfa, refresh_user_token, logout_user, verify_email, resend_verification,
+    forgot_password, reset_password,
+};
+use crate::handlers::oidc_handler::{oidc_login, oidc_callback};
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/auth")
             .service(register_user)
             .service(login_user)
+            .service(login_mfa)
+            .service(refresh_user_token)
+            .service(logout_user)
+            .service(verify_email)
+            .service(resend_verification)
+            .service(forgot_password)
+            .service(reset_password)
+            .service(oidc_login)
+            .service(oidc_callback)
     );
 }
@@ -1,35 +1,156 @@
-use actix_web::{web, get, post, HttpRequest, Responder};
-use actix_web::HttpMessage;
-use crate::middleware::auth_middleware::Claims;
-use crate::handlers::company_handler::{create_company, get_all_companies};
-use crate::models::company::CreateCompany;
+use actix_web::{web, get, post, put, patch, delete, Responder};
+use crate::handlers::company_handler::{
+    create_company, get_all_companies, get_company_by_id, update_company, delete_company,
+    upload_company_media, delete_company_media, get_company_members, add_company_member, remove_company_member,
+};
+use crate::middleware::extractors::{
+    AuthUser, RequireRole, Employer, RequireCompanyRole, CompanyViewer, CompanyOwner,
+};
+use crate::models::company::{CreateCompany, UpdateCompany, DeleteCompanyQuery, AddCompanyMember, CompanyMediaKind};
+use crate::utils::storage::MediaStorage;
+use actix_multipart::Multipart;
 
 #[get("/")]
-pub async fn list_companies(req: HttpRequest, db: web::Data<sqlx::MySqlPool>) -> impl Responder {
-    let req_clone = req.clone();
-    let extensions = req_clone.extensions();
-    let claims = extensions.get::<Claims>().unwrap();
-
-    get_all_companies(req, db).await
+pub async fn list_companies(user: AuthUser, db: web::Data<sqlx::MySqlPool>) -> impl Responder {
+    get_all_companies(user, db).await
 }
 
 #[post("/")]
 pub async fn create_company_route(
-    req: HttpRequest,
+    employer: RequireRole<Employer>,
     db: web::Data<sqlx::MySqlPool>,
     body: web::Json<CreateCompany>,
 ) -> impl Responder {
-    let req_clone = req.clone();
-    let extensions = req_clone.extensions();
-    let claims = extensions.get::<Claims>().unwrap();
+    create_company(employer, db, body).await
+}
+
+#[get("/{id}")]
+pub async fn get_company(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    company_id: web::Path<i32>,
+) -> impl Responder {
+    get_company_by_id(user, db, company_id).await
+}
+
+/// PUT /api/companies/{id} — replace all editable fields
+#[put("/{id}")]
+pub async fn replace_company(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    company_id: web::Path<i32>,
+    body: web::Json<UpdateCompany>,
+) -> impl Responder {
+    update_company(user, db, company_id, body, true).await
+}
+
+/// PATCH /api/companies/{id} — change only the fields provided
+#[patch("/{id}")]
+pub async fn patch_company(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    company_id: web::Path<i32>,
+    body: web::Json<UpdateCompany>,
+) -> impl Responder {
+    update_company(user, db, company_id, body, false).await
+}
+
+/// DELETE /api/companies/{id} — `?cascade=true` also removes the company's jobs
+#[delete("/{id}")]
+pub async fn delete_company_route(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    company_id: web::Path<i32>,
+    query: web::Query<DeleteCompanyQuery>,
+) -> impl Responder {
+    delete_company(user, db, company_id, query).await
+}
+
+/// POST /api/companies/{id}/logo — multipart upload, field `file`
+#[post("/{id}/logo")]
+pub async fn upload_logo(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    storage: web::Data<dyn MediaStorage>,
+    company_id: web::Path<i32>,
+    payload: Multipart,
+) -> impl Responder {
+    upload_company_media(user, db, storage, company_id, payload, CompanyMediaKind::Logo).await
+}
+
+#[delete("/{id}/logo")]
+pub async fn delete_logo(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    storage: web::Data<dyn MediaStorage>,
+    company_id: web::Path<i32>,
+) -> impl Responder {
+    delete_company_media(user, db, storage, company_id, CompanyMediaKind::Logo).await
+}
+
+/// POST /api/companies/{id}/cover — multipart upload, field `file`
+#[post("/{id}/cover")]
+pub async fn upload_cover(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    storage: web::Data<dyn MediaStorage>,
+    company_id: web::Path<i32>,
+    payload: Multipart,
+) -> impl Responder {
+    upload_company_media(user, db, storage, company_id, payload, CompanyMediaKind::Cover).await
+}
+
+#[delete("/{id}/cover")]
+pub async fn delete_cover(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    storage: web::Data<dyn MediaStorage>,
+    company_id: web::Path<i32>,
+) -> impl Responder {
+    delete_company_media(user, db, storage, company_id, CompanyMediaKind::Cover).await
+}
+
+#[get("/{id}/members")]
+pub async fn list_members(
+    member: RequireCompanyRole<CompanyViewer>,
+    db: web::Data<sqlx::MySqlPool>,
+) -> impl Responder {
+    get_company_members(member, db).await
+}
 
-    create_company(req, db, body).await
+#[post("/{id}/members")]
+pub async fn add_member(
+    owner: RequireCompanyRole<CompanyOwner>,
+    db: web::Data<sqlx::MySqlPool>,
+    body: web::Json<AddCompanyMember>,
+) -> impl Responder {
+    add_company_member(owner, db, body).await
+}
+
+#[delete("/{id}/members/{user_id}")]
+pub async fn remove_member(
+    owner: RequireCompanyRole<CompanyOwner>,
+    db: web::Data<sqlx::MySqlPool>,
+    path: web::Path<(i32, i32)>,
+) -> impl Responder {
+    remove_company_member(owner, db, path).await
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/companies")
             .service(list_companies)
-            .service(create_company_route),
+            .service(create_company_route)
+            .service(get_company)
+            .service(replace_company)
+            .service(patch_company)
+            .service(delete_company_route)
+            .service(upload_logo)
+            .service(delete_logo)
+            .service(upload_cover)
+            .service(delete_cover)
+            .service(list_members)
+            .service(add_member)
+            .service(remove_member),
     );
 }
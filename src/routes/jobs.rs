@@ -1,45 +1,102 @@
-use actix_web::{web, get, post, delete, put, HttpRequest, Responder};
-use crate::middleware::auth_middleware::Claims;
-use crate::handlers::job_handler::{create_job, get_all_jobs, delete_job, increment_job_view, get_job_by_id};
-use crate::models::job::CreateJob;
+use actix_web::{web, get, post, delete, put, patch, Responder};
+use crate::middleware::extractors::{AuthUser, RequireRole, Employer};
+use crate::handlers::job_handler::{
+    create_job, get_all_jobs, delete_job, increment_job_view, get_job_by_id, update_job, transition_job,
+    publish_job,
+};
+use crate::models::job::{CreateJob, UpdateJob, JobStatus};
 
 /// Route for GET /api/jobs — with query parameters (page, limit, search)
 #[get("/")]
 async fn list_jobs(
-    req: HttpRequest,
+    user: AuthUser,
     db: web::Data<sqlx::MySqlPool>,
     query: web::Query<crate::models::job::JobQueryParams>,
 ) -> impl Responder {
-    get_all_jobs(req, db, query).await
+    get_all_jobs(user, db, query).await
 }
 
 /// Route for POST /api/jobs
 #[post("/")]
 async fn create_job_route(
-    req: HttpRequest,
+    employer: RequireRole<Employer>,
     db: web::Data<sqlx::MySqlPool>,
     body: web::Json<CreateJob>,
 ) -> impl Responder {
-    create_job(req, db, body).await
+    create_job(employer, db, body).await
 }
 
 /// Route for DELETE /api/jobs/{id}
 #[delete("/{id}")]
 async fn delete_job_route(
-    req: HttpRequest,
+    user: AuthUser,
     db: web::Data<sqlx::MySqlPool>,
     job_id: web::Path<i32>,
 ) -> impl Responder {
-    delete_job(req, db, job_id).await
+    delete_job(user, db, job_id).await
+}
+
+/// Route for PUT /api/jobs/{id} — replace all editable fields
+#[put("/{id}")]
+async fn replace_job_route(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    job_id: web::Path<i32>,
+    body: web::Json<UpdateJob>,
+) -> impl Responder {
+    update_job(user, db, job_id, body, true).await
+}
+
+/// Route for PATCH /api/jobs/{id} — change only the fields provided
+#[patch("/{id}")]
+async fn patch_job_route(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    job_id: web::Path<i32>,
+    body: web::Json<UpdateJob>,
+) -> impl Responder {
+    update_job(user, db, job_id, body, false).await
+}
+
+/// Route for POST /api/jobs/{id}/publish — optional body `{ "expires_at": ... }`
+#[post("/{id}/publish")]
+async fn publish_job_route(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    job_id: web::Path<i32>,
+    body: web::Bytes,
+) -> impl Responder {
+    publish_job(user, db, job_id, body).await
+}
+
+/// Route for POST /api/jobs/{id}/pause
+#[post("/{id}/pause")]
+async fn pause_job_route(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    job_id: web::Path<i32>,
+) -> impl Responder {
+    transition_job(user, db, job_id, JobStatus::Paused, None).await
+}
+
+/// Route for POST /api/jobs/{id}/close
+#[post("/{id}/close")]
+async fn close_job_route(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    job_id: web::Path<i32>,
+) -> impl Responder {
+    transition_job(user, db, job_id, JobStatus::Closed, None).await
 }
 
 /// Route for GET /api/jobs/{id}
 #[get("/{id}")]
 async fn get_job_route(
+    user: AuthUser,
     db: web::Data<sqlx::MySqlPool>,
     job_id: web::Path<i32>,
 ) -> impl Responder {
-    get_job_by_id(db, job_id).await
+    get_job_by_id(user, db, job_id).await
 }
 
 // /// PUT /api/jobs/{id}/view — increment job view count manually (if needed)
@@ -58,6 +115,11 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .service(list_jobs)
             .service(create_job_route)
             .service(delete_job_route)
+            .service(replace_job_route)
+            .service(patch_job_route)
+            .service(publish_job_route)
+            .service(pause_job_route)
+            .service(close_job_route)
             .service(get_job_route)
             .service(increment_job_view)
             // .service(apply_to_job)
@@ -3,4 +3,7 @@ pub mod jobs;
 pub mod companies;
 pub mod users;
 pub mod applications;
-pub mod admins;
\ No newline at end of file
+pub mod admins;
+pub mod public_jobs;
+pub mod well_known;
+pub mod api_keys;
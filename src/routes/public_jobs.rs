@@ -0,0 +1,30 @@
+use actix_web::{web, get, Responder};
+use crate::handlers::job_handler::{get_public_jobs, get_public_job_by_id};
+use crate::models::job::JobQueryParams;
+
+/// Route for GET /public/jobs — anonymous browsing with filters (page, limit, search, ...)
+#[get("")]
+async fn list_public_jobs(
+    db: web::Data<sqlx::MySqlPool>,
+    query: web::Query<JobQueryParams>,
+) -> impl Responder {
+    get_public_jobs(db, query).await
+}
+
+/// Route for GET /public/jobs/{id}
+#[get("/{id}")]
+async fn get_public_job(
+    db: web::Data<sqlx::MySqlPool>,
+    job_id: web::Path<i32>,
+) -> impl Responder {
+    get_public_job_by_id(db, job_id).await
+}
+
+/// Register the unauthenticated /public/jobs routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/public/jobs")
+            .service(list_public_jobs)
+            .service(get_public_job)
+    );
+}
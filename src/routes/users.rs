@@ -1,39 +1,115 @@
-use actix_web::{web, get, delete};
+use actix_web::{web, get, post, delete, HttpRequest};
 use crate::handlers::user_handler::{
     get_current_user,
     get_all_users,
     delete_user_by_id,
+    change_password,
+    get_my_sessions,
+    revoke_my_session,
 };
+use crate::handlers::mfa_handler::{setup_totp, confirm_totp, disable_totp, regenerate_recovery_codes};
+use crate::models::auth::{ChangePasswordRequest, TotpCodeRequest, DisableTotpRequest};
+use crate::middleware::extractors::{AuthUser, RequireRole, Admin, Employer};
 
 #[get("/me")]
 async fn get_me(
-    req: actix_web::HttpRequest,
+    user: AuthUser,
     db: web::Data<sqlx::MySqlPool>,
 ) -> impl actix_web::Responder {
-    get_current_user(req, db).await
+    get_current_user(user, db).await
+}
+
+#[post("/me/password")]
+async fn change_my_password(
+    req: HttpRequest,
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    body: web::Json<ChangePasswordRequest>,
+) -> impl actix_web::Responder {
+    change_password(req, user, db, body).await
+}
+
+#[get("/me/sessions")]
+async fn list_my_sessions(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+) -> impl actix_web::Responder {
+    get_my_sessions(user, db).await
+}
+
+#[delete("/me/sessions/{id}")]
+async fn delete_my_session(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    session_id: web::Path<i32>,
+) -> impl actix_web::Responder {
+    revoke_my_session(user, db, session_id).await
+}
+
+#[post("/me/mfa/totp/setup")]
+async fn setup_my_totp(
+    user: RequireRole<Employer>,
+    db: web::Data<sqlx::MySqlPool>,
+) -> impl actix_web::Responder {
+    setup_totp(user, db).await
+}
+
+#[post("/me/mfa/totp/confirm")]
+async fn confirm_my_totp(
+    user: RequireRole<Employer>,
+    db: web::Data<sqlx::MySqlPool>,
+    body: web::Json<TotpCodeRequest>,
+) -> impl actix_web::Responder {
+    confirm_totp(user, db, body).await
+}
+
+#[delete("/me/mfa/totp")]
+async fn disable_my_totp(
+    req: HttpRequest,
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    body: web::Json<DisableTotpRequest>,
+) -> impl actix_web::Responder {
+    disable_totp(req, user, db, body).await
+}
+
+#[post("/me/mfa/recovery-codes")]
+async fn regenerate_my_recovery_codes(
+    user: AuthUser,
+    db: web::Data<sqlx::MySqlPool>,
+    body: web::Json<TotpCodeRequest>,
+) -> impl actix_web::Responder {
+    regenerate_recovery_codes(user, db, body).await
 }
 
 #[get("/")]
 async fn list_users(
-    req: actix_web::HttpRequest,
+    admin: RequireRole<Admin>,
     db: web::Data<sqlx::MySqlPool>,
 ) -> impl actix_web::Responder {
-    get_all_users(req, db).await
+    get_all_users(admin, db).await
 }
 
 #[delete("/{id}")]
 async fn delete_user(
-    req: actix_web::HttpRequest,
+    admin: RequireRole<Admin>,
     db: web::Data<sqlx::MySqlPool>,
     user_id: web::Path<i32>,
 ) -> impl actix_web::Responder {
-    delete_user_by_id(req, db, user_id).await
+    delete_user_by_id(admin, db, user_id).await
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/users")
             .service(get_me)
+            .service(change_my_password)
+            .service(list_my_sessions)
+            .service(delete_my_session)
+            .service(setup_my_totp)
+            .service(confirm_my_totp)
+            .service(disable_my_totp)
+            .service(regenerate_my_recovery_codes)
             .service(list_users)
             .service(delete_user),
     );
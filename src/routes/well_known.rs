@@ -0,0 +1,17 @@
+use actix_web::{web, get, Responder};
+use crate::handlers::auth_handler::get_jwks;
+use crate::utils::jwt::JwtKeys;
+
+/// Route for GET /.well-known/jwks.json — public keys that verify access tokens
+#[get("/jwks.json")]
+async fn jwks(keys: web::Data<JwtKeys>) -> impl Responder {
+    get_jwks(keys).await
+}
+
+/// Register the unauthenticated /.well-known routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/.well-known")
+            .service(jwks)
+    );
+}
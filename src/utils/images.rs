@@ -0,0 +1,255 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageError, ImageFormat, ImageReader, Limits};
+use serde_json::json;
+use std::io::Cursor;
+
+use crate::errors::ApiError;
+
+/// Content types accepted for uploads
+const ACCEPTED_TYPES: [(&str, ImageFormat); 3] = [
+    ("image/png", ImageFormat::Png),
+    ("image/jpeg", ImageFormat::Jpeg),
+    ("image/webp", ImageFormat::WebP),
+];
+
+/// Ceiling on what the decoder may allocate for one upload. An RGBA8 bitmap at
+/// the largest accepted size (4096×4096) needs 64 MiB; 16-bit images that big
+/// are refused rather than decoded.
+const MAX_DECODE_BYTES: u64 = 80 * 1024 * 1024;
+
+/// How an upload is checked and which resized copies are made of it
+pub struct ImageSpec {
+    pub min_width: u32,
+    pub min_height: u32,
+    pub max_width: u32,
+    pub max_height: u32,
+    /// Longest side over shortest side
+    pub max_aspect_ratio: f64,
+    /// Variant name and the box it is scaled down to fit
+    pub variants: &'static [(&'static str, u32, u32)],
+    /// Output encoding: PNG keeps transparency, JPEG keeps photos small
+    pub output: ImageFormat,
+}
+
+/// Company logos: roughly square (at most 2:1), rendered small
+pub const LOGO: ImageSpec = ImageSpec {
+    min_width: 64,
+    min_height: 64,
+    max_width: 4096,
+    max_height: 4096,
+    max_aspect_ratio: 2.0,
+    variants: &[("large", 512, 512), ("medium", 256, 256), ("small", 64, 64)],
+    output: ImageFormat::Png,
+};
+
+/// Company page banners: wide photos
+pub const COVER: ImageSpec = ImageSpec {
+    min_width: 800,
+    min_height: 200,
+    max_width: 4096,
+    max_height: 4096,
+    max_aspect_ratio: 5.0,
+    variants: &[("large", 1600, 1600), ("medium", 800, 800)],
+    output: ImageFormat::Jpeg,
+};
+
+/// One encoded, resized copy of an upload
+pub struct ImageVariant {
+    pub name: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub content_type: &'static str,
+    pub extension: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+fn invalid_file(message: String) -> ApiError {
+    ApiError::validation("Invalid image", json!({ "file": [message] }))
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ApiError> {
+    let mut bytes = Vec::new();
+    let written = match format {
+        // JPEG has no alpha channel
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, 85)),
+        _ => image.write_to(&mut Cursor::new(&mut bytes), format),
+    };
+
+    written.map_err(|e| {
+        eprintln!("Error encoding image: {:?}", e);
+        ApiError::Internal
+    })?;
+    Ok(bytes)
+}
+
+/// Validate an upload and produce its variants, largest first. The file's
+/// bytes must match the declared content type, which must be PNG, JPEG or
+/// WebP. Re-encoding also drops any metadata (EXIF, GPS) the original carried.
+///
+/// CPU-bound: call it from `web::block`.
+pub fn process_image(bytes: &[u8], declared_type: &str, spec: &ImageSpec) -> Result<Vec<ImageVariant>, ApiError> {
+    let expected = ACCEPTED_TYPES
+        .iter()
+        .find(|(mime, _)| *mime == declared_type)
+        .map(|(_, format)| *format)
+        .ok_or_else(|| ApiError::unsupported_media_type("Upload a PNG, JPEG or WebP image"))?;
+
+    let open = || {
+        ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|_| invalid_file("could not be read".to_string()))
+    };
+    let reader = open()?;
+    if reader.format() != Some(expected) {
+        return Err(ApiError::unsupported_media_type("File contents do not match its content type"));
+    }
+
+    // Check the header's dimensions before decoding a possibly huge bitmap
+    let (width, height) = reader
+        .into_dimensions()
+        .map_err(|_| invalid_file("is not a valid image".to_string()))?;
+    if width < spec.min_width || height < spec.min_height {
+        return Err(invalid_file(format!(
+            "must be at least {}x{} pixels",
+            spec.min_width, spec.min_height
+        )));
+    }
+    if width > spec.max_width || height > spec.max_height {
+        return Err(invalid_file(format!(
+            "must be at most {}x{} pixels",
+            spec.max_width, spec.max_height
+        )));
+    }
+    let aspect_ratio = width.max(height) as f64 / width.min(height) as f64;
+    if aspect_ratio > spec.max_aspect_ratio {
+        return Err(invalid_file(format!(
+            "must have an aspect ratio of at most {}:1",
+            spec.max_aspect_ratio
+        )));
+    }
+
+    let mut reader = open()?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(spec.max_width);
+    limits.max_image_height = Some(spec.max_height);
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+    reader.limits(limits);
+    let image = reader.decode().map_err(|e| match e {
+        ImageError::Limits(_) => invalid_file("is too large to process".to_string()),
+        _ => invalid_file("is not a valid image".to_string()),
+    })?;
+
+    let (content_type, extension) = match spec.output {
+        ImageFormat::Jpeg => ("image/jpeg", "jpg"),
+        _ => ("image/png", "png"),
+    };
+
+    spec.variants
+        .iter()
+        .map(|&(name, max_width, max_height)| {
+            // Only ever scale down. `thumbnail` averages in integer space, so it
+            // needs no full-size floating-point buffer the way `resize` does.
+            let resized = if image.width() <= max_width && image.height() <= max_height {
+                image.clone()
+            } else {
+                image.thumbnail(max_width, max_height)
+            };
+
+            Ok(ImageVariant {
+                name,
+                width: resized.width(),
+                height: resized.height(),
+                content_type,
+                extension,
+                bytes: encode(&resized, spec.output)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::new_luma8(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn sizes(variants: &[ImageVariant]) -> Vec<(&str, u32, u32)> {
+        variants.iter().map(|v| (v.name, v.width, v.height)).collect()
+    }
+
+    /// The message reported for the `file` field, if validation failed
+    fn file_error(result: Result<Vec<ImageVariant>, ApiError>) -> String {
+        match result {
+            Err(ApiError::Validation { details, .. }) => details["file"][0].as_str().unwrap().to_string(),
+            Err(other) => panic!("expected a validation error, got {:?}", other),
+            Ok(_) => panic!("expected a validation error, got variants"),
+        }
+    }
+
+    fn is_unsupported(result: Result<Vec<ImageVariant>, ApiError>) -> bool {
+        matches!(result, Err(ApiError::UnsupportedMediaType(_)))
+    }
+
+    #[test]
+    fn logo_is_resized_into_png_variants() {
+        let variants = process_image(&png(600, 600), "image/png", &LOGO).ok().unwrap();
+
+        assert_eq!(sizes(&variants), [("large", 512, 512), ("medium", 256, 256), ("small", 64, 64)]);
+        assert!(variants.iter().all(|v| v.content_type == "image/png" && v.extension == "png"));
+        let decoded = image::load_from_memory(&variants[0].bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (512, 512));
+    }
+
+    #[test]
+    fn cover_keeps_its_aspect_ratio_and_is_never_upscaled() {
+        let variants = process_image(&png(1000, 400), "image/png", &COVER).ok().unwrap();
+
+        assert_eq!(sizes(&variants), [("large", 1000, 400), ("medium", 800, 320)]);
+        assert!(variants.iter().all(|v| v.content_type == "image/jpeg"));
+        assert_eq!(image::guess_format(&variants[1].bytes).unwrap(), ImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn rejects_content_types_other_than_png_jpeg_and_webp() {
+        assert!(is_unsupported(process_image(&png(100, 100), "image/gif", &LOGO)));
+        assert!(is_unsupported(process_image(&png(100, 100), "", &LOGO)));
+    }
+
+    #[test]
+    fn rejects_bytes_that_do_not_match_the_content_type() {
+        assert!(is_unsupported(process_image(&png(100, 100), "image/jpeg", &LOGO)));
+        assert!(is_unsupported(process_image(b"<svg></svg>", "image/png", &LOGO)));
+    }
+
+    #[test]
+    fn rejects_images_outside_the_size_limits() {
+        assert_eq!(
+            file_error(process_image(&png(32, 32), "image/png", &LOGO)),
+            "must be at least 64x64 pixels"
+        );
+        assert_eq!(
+            file_error(process_image(&png(4097, 4000), "image/png", &LOGO)),
+            "must be at most 4096x4096 pixels"
+        );
+        assert_eq!(
+            file_error(process_image(&png(1000, 150), "image/png", &COVER)),
+            "must be at least 800x200 pixels"
+        );
+    }
+
+    #[test]
+    fn rejects_logos_that_are_far_from_square() {
+        assert_eq!(
+            file_error(process_image(&png(600, 200), "image/png", &LOGO)),
+            "must have an aspect ratio of at most 2:1"
+        );
+        assert!(process_image(&png(400, 200), "image/png", &LOGO).is_ok());
+    }
+}
@@ -0,0 +1,273 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::errors::{Error, ErrorKind};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+use crate::middleware::auth_middleware::Claims;
+
+/// Tolerated clock drift when checking `iat`
+const IAT_LEEWAY_SECONDS: usize = 60;
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo; the raw 32-byte key follows it
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+/// A key access tokens may be verified with
+struct VerifyingKey {
+    algorithm: Algorithm,
+    decoding: DecodingKey,
+    /// Public form for the JWKS document; `None` for shared secrets
+    jwk: Option<Jwk>,
+}
+
+/// Signing and verification keys for access tokens, loaded once at startup and
+/// shared as app data.
+///
+/// Configuration:
+/// - JWT_ALGORITHM: `HS256` (default), `RS256` or `EdDSA`
+/// - JWT_KEY_ID: `kid` of the active key (default `primary`)
+/// - HS256: JWT_SECRET, plus JWT_RETIRED_SECRETS (`kid=secret,...`)
+/// - RS256/EdDSA: JWT_PRIVATE_KEY_PATH and JWT_PUBLIC_KEY_PATH (PEM), plus
+///   JWT_RETIRED_PUBLIC_KEYS (`kid=/path/to/key.pem,...`)
+/// - JWT_ISSUER / JWT_AUDIENCE: expected `iss` / `aud` (default `job_board_api`)
+///
+/// To rotate, move the active key into the retired list, configure the new one
+/// under a new `kid`, and drop the retired entry once the access token TTL has passed.
+pub struct JwtKeys {
+    kid: String,
+    algorithm: Algorithm,
+    encoding: EncodingKey,
+    verifying: HashMap<String, VerifyingKey>,
+    pub issuer: String,
+    pub audience: String,
+}
+
+fn required_env(key: &str) -> String {
+    env::var(key).unwrap_or_else(|_| panic!("{} must be set", key))
+}
+
+fn read_key_file(path: &str) -> String {
+    fs::read_to_string(path).unwrap_or_else(|e| panic!("Cannot read JWT key {}: {}", path, e))
+}
+
+/// `kid=value` pairs from a comma-separated variable
+fn keyed_list(key: &str) -> Vec<(String, String)> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (kid, value) = entry
+                .split_once('=')
+                .unwrap_or_else(|| panic!("{} entries must look like kid=value", key));
+            (kid.trim().to_string(), value.trim().to_string())
+        })
+        .collect()
+}
+
+fn b64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Parse a public key PEM (RSA in SPKI or PKCS#1 form, or Ed25519 in SPKI form)
+/// into its verification key and JWK
+fn public_key_from_pem(kid: &str, pem_text: &str) -> VerifyingKey {
+    let rsa_key = RsaPublicKey::from_public_key_pem(pem_text)
+        .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem_text));
+
+    let (algorithm, params) = if let Ok(rsa_key) = rsa_key {
+        let params = AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: b64(&rsa_key.n().to_bytes_be()),
+            e: b64(&rsa_key.e().to_bytes_be()),
+        });
+        (Algorithm::RS256, params)
+    } else {
+        let der = pem::parse(pem_text)
+            .unwrap_or_else(|e| panic!("JWT key {} is not valid PEM: {}", kid, e));
+        let raw = der
+            .contents()
+            .strip_prefix(&ED25519_SPKI_PREFIX[..])
+            .filter(|raw| raw.len() == 32)
+            .unwrap_or_else(|| panic!("JWT key {} is neither an RSA nor an Ed25519 public key", kid));
+        let params = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: b64(raw),
+        });
+        (Algorithm::EdDSA, params)
+    };
+
+    let jwk = Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(match algorithm {
+                Algorithm::RS256 => KeyAlgorithm::RS256,
+                _ => KeyAlgorithm::EdDSA,
+            }),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: params,
+    };
+    let decoding = DecodingKey::from_jwk(&jwk)
+        .unwrap_or_else(|e| panic!("JWT key {} is unusable: {}", kid, e));
+
+    VerifyingKey { algorithm, decoding, jwk: Some(jwk) }
+}
+
+fn secret_key(secret: &str) -> VerifyingKey {
+    VerifyingKey {
+        algorithm: Algorithm::HS256,
+        decoding: DecodingKey::from_secret(secret.as_bytes()),
+        jwk: None,
+    }
+}
+
+impl JwtKeys {
+    /// Load every key named by the environment. Panics on a missing or unusable
+    /// key so misconfiguration stops the server at boot, not on the first request.
+    pub fn from_env() -> Self {
+        let kid = env::var("JWT_KEY_ID").unwrap_or_else(|_| "primary".to_string());
+        let mut verifying = HashMap::new();
+
+        let (algorithm, encoding, retired) = match env::var("JWT_ALGORITHM").as_deref() {
+            Ok("HS256") | Err(_) => {
+                let secret = required_env("JWT_SECRET");
+                verifying.insert(kid.clone(), secret_key(&secret));
+                let retired: Vec<(String, VerifyingKey)> = keyed_list("JWT_RETIRED_SECRETS")
+                    .into_iter()
+                    .map(|(kid, secret)| (kid, secret_key(&secret)))
+                    .collect();
+                (Algorithm::HS256, EncodingKey::from_secret(secret.as_bytes()), retired)
+            }
+            Ok(name @ ("RS256" | "EdDSA")) => {
+                let algorithm = if name == "RS256" { Algorithm::RS256 } else { Algorithm::EdDSA };
+                let private_pem = read_key_file(&required_env("JWT_PRIVATE_KEY_PATH"));
+                let encoding = if algorithm == Algorithm::RS256 {
+                    EncodingKey::from_rsa_pem(private_pem.as_bytes())
+                } else {
+                    EncodingKey::from_ed_pem(private_pem.as_bytes())
+                }
+                .unwrap_or_else(|e| panic!("JWT_PRIVATE_KEY_PATH is not a usable {} key: {}", name, e));
+
+                let public = public_key_from_pem(&kid, &read_key_file(&required_env("JWT_PUBLIC_KEY_PATH")));
+                if public.algorithm != algorithm {
+                    panic!("JWT_PUBLIC_KEY_PATH does not hold a {} key", name);
+                }
+                verifying.insert(kid.clone(), public);
+
+                let retired: Vec<(String, VerifyingKey)> = keyed_list("JWT_RETIRED_PUBLIC_KEYS")
+                    .into_iter()
+                    .map(|(kid, path)| {
+                        let key = public_key_from_pem(&kid, &read_key_file(&path));
+                        (kid, key)
+                    })
+                    .collect();
+                (algorithm, encoding, retired)
+            }
+            Ok(other) => panic!("Unsupported JWT_ALGORITHM: {}", other),
+        };
+
+        for (retired_kid, key) in retired {
+            if verifying.insert(retired_kid.clone(), key).is_some() {
+                panic!("JWT key id {} is configured twice", retired_kid);
+            }
+        }
+
+        let keys = JwtKeys {
+            kid,
+            algorithm,
+            encoding,
+            verifying,
+            issuer: env::var("JWT_ISSUER").unwrap_or_else(|_| "job_board_api".to_string()),
+            audience: env::var("JWT_AUDIENCE").unwrap_or_else(|_| "job_board_api".to_string()),
+        };
+        keys.check_key_pair();
+        keys
+    }
+
+    /// HS256 keys from a literal secret and the default issuer and audience, so
+    /// tests need neither key files nor environment variables
+    #[cfg(test)]
+    pub fn from_secret(secret: &str) -> Self {
+        let kid = "primary".to_string();
+        JwtKeys {
+            verifying: HashMap::from([(kid.clone(), secret_key(secret))]),
+            kid,
+            algorithm: Algorithm::HS256,
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            issuer: "job_board_api".to_string(),
+            audience: "job_board_api".to_string(),
+        }
+    }
+
+    /// Sign a probe with the private key and verify it with the published public
+    /// key, catching a mismatched pair at startup
+    fn check_key_pair(&self) {
+        let probe = serde_json::json!({ "probe": true });
+        let token = jsonwebtoken::encode(&self.header(), &probe, &self.encoding)
+            .unwrap_or_else(|e| panic!("Cannot sign with the active JWT key: {}", e));
+
+        let mut validation = Validation::new(self.algorithm);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        let key = &self.verifying[&self.kid];
+        if let Err(e) = jsonwebtoken::decode::<serde_json::Value>(&token, &key.decoding, &validation) {
+            panic!("JWT private and public keys do not match: {}", e);
+        }
+    }
+
+    fn header(&self) -> Header {
+        let mut header = Header::new(self.algorithm);
+        header.kid = Some(self.kid.clone());
+        header
+    }
+
+    /// Sign claims with the active key, tagging the token with its `kid`
+    pub fn encode(&self, claims: &Claims) -> Result<String, Error> {
+        jsonwebtoken::encode(&self.header(), claims, &self.encoding)
+    }
+
+    /// Verify a token against the key its `kid` names, checking the algorithm,
+    /// `exp`, `iss`, `aud` and that `iat` is not in the future
+    pub fn decode(&self, token: &str) -> Result<Claims, Error> {
+        let header = jsonwebtoken::decode_header(token)?;
+        let key = header
+            .kid
+            .as_deref()
+            .and_then(|kid| self.verifying.get(kid))
+            .ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iat", "iss", "aud", "sub"]);
+
+        let claims = jsonwebtoken::decode::<Claims>(token, &key.decoding, &validation)?.claims;
+
+        let now = jsonwebtoken::get_current_timestamp() as usize;
+        if claims.iat > now + IAT_LEEWAY_SECONDS {
+            return Err(ErrorKind::ImmatureSignature.into());
+        }
+        Ok(claims)
+    }
+
+    /// Public halves of every asymmetric verification key, retired ones included
+    pub fn jwks(&self) -> JwkSet {
+        let mut keys: Vec<Jwk> = self.verifying.values().filter_map(|key| key.jwk.clone()).collect();
+        keys.sort_by(|a, b| a.common.key_id.cmp(&b.common.key_id));
+        JwkSet { keys }
+    }
+}
@@ -0,0 +1,84 @@
+use std::env;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// An outgoing plain-text email
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "To: {}\nSubject: {}\n\n{}\n", self.to, self.subject, self.body)
+    }
+}
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl fmt::Display for MailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Delivery backend for transactional mail. Handlers take it as
+/// `web::Data<dyn Mailer>` so the transport can be swapped without touching them.
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+/// Development sink: prints every message to stdout
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        println!("📧 ----- outgoing email -----\n{}", email);
+        Ok(())
+    }
+}
+
+/// Development sink: appends every message to a local file
+pub struct FileMailer {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileMailer {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileMailer { path: path.into(), lock: Mutex::new(()) }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        let _guard = self.lock.lock().map_err(|e| MailError(e.to_string()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| MailError(format!("{}: {}", self.path.display(), e)))?;
+
+        writeln!(file, "----- {} -----\n{}", chrono::Utc::now().to_rfc3339(), email)
+            .map_err(|e| MailError(e.to_string()))
+    }
+}
+
+/// Pick the transport from MAIL_TRANSPORT (`log`, the default, or `file`,
+/// which writes to MAIL_FILE_PATH, default `mail.log`)
+pub fn mailer_from_env() -> Arc<dyn Mailer> {
+    match env::var("MAIL_TRANSPORT").as_deref() {
+        Ok("file") => {
+            let path = env::var("MAIL_FILE_PATH").unwrap_or_else(|_| "mail.log".to_string());
+            Arc::new(FileMailer::new(path))
+        }
+        Ok("log") | Err(_) => Arc::new(LogMailer),
+        Ok(other) => panic!("Unsupported MAIL_TRANSPORT: {}", other),
+    }
+}
@@ -0,0 +1,9 @@
+pub mod images;
+pub mod jwt;
+pub mod mailer;
+pub mod oidc;
+pub mod passwords;
+pub mod storage;
+pub mod tokens;
+pub mod totp;
+pub mod validation;
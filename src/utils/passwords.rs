@@ -0,0 +1,94 @@
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use argon2::password_hash::{SaltString, rand_core::OsRng};
+use crate::errors::ApiError;
+use std::env;
+use std::sync::OnceLock;
+
+fn env_u32(key: &str, default: u32) -> u32 {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Cost parameters for new hashes: ARGON2_MEMORY_KIB (default 19456),
+/// ARGON2_ITERATIONS (default 2) and ARGON2_PARALLELISM (default 1).
+/// Read once; panics on values argon2 rejects so a bad config fails at startup.
+pub fn argon2_params() -> &'static Params {
+    static PARAMS: OnceLock<Params> = OnceLock::new();
+    PARAMS.get_or_init(|| {
+        Params::new(
+            env_u32("ARGON2_MEMORY_KIB", Params::DEFAULT_M_COST),
+            env_u32("ARGON2_ITERATIONS", Params::DEFAULT_T_COST),
+            env_u32("ARGON2_PARALLELISM", Params::DEFAULT_P_COST),
+            None,
+        )
+        .expect("Invalid ARGON2_* password hashing parameters")
+    })
+}
+
+/// The hasher every new password goes through: Argon2id v19 with the configured costs
+fn hasher() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params().clone())
+}
+
+/// Hash a password for storage in `users.password_hash`
+pub fn hash_password(password: &str) -> Result<String, ApiError> {
+    let salt = SaltString::generate(&mut OsRng);
+    hasher()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| {
+            eprintln!("Error hashing password: {:?}", e);
+            ApiError::Internal
+        })
+}
+
+/// Check a password against a stored hash. An unreadable hash is logged and
+/// treated as a mismatch rather than failing the request.
+pub fn verify_password(password: &str, stored_hash: &str) -> bool {
+    let parsed = match PasswordHash::new(stored_hash) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("Stored password hash is unreadable: {:?}", e);
+            return false;
+        }
+    };
+
+    // Verification takes algorithm and costs from the stored hash itself
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok()
+}
+
+/// Whether a stored hash predates the current policy (another argon2 variant,
+/// an older version or different costs) and should be replaced
+pub fn needs_rehash(stored_hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(stored_hash) else {
+        return true;
+    };
+
+    if parsed.algorithm != Algorithm::Argon2id.ident()
+        || parsed.version != Some(Version::V0x13.into())
+    {
+        return true;
+    }
+
+    let current = argon2_params();
+    match Params::try_from(&parsed) {
+        Ok(params) => {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        }
+        Err(_) => true,
+    }
+}
+
+/// Burn the same time as a real verification when there is no account to check,
+/// so response timing does not reveal which emails are registered
+pub fn dummy_verify(password: &str) {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hash = DUMMY_HASH.get_or_init(|| {
+        hash_password("dummy password for timing equalization").unwrap_or_default()
+    });
+
+    let _ = verify_password(password, hash);
+}
@@ -0,0 +1,14 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Generate a random opaque token (256 bits, hex encoded)
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// SHA-256 digest of a token, hex encoded — only the digest is ever stored
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
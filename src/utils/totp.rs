@@ -0,0 +1,77 @@
+use crate::errors::ApiError;
+use rand::RngCore;
+use std::env;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// RFC 6238 defaults understood by every authenticator app
+const DIGITS: usize = 6;
+const STEP_SECONDS: u64 = 30;
+/// Codes from one step either side of now are accepted to absorb clock drift
+const SKEW_STEPS: i64 = 1;
+
+/// Name shown next to the account in authenticator apps (MFA_ISSUER, default `Job Board`)
+fn issuer() -> String {
+    env::var("MFA_ISSUER").unwrap_or_else(|_| "Job Board".to_string())
+}
+
+fn build(secret: &str, account: &str) -> Result<TOTP, ApiError> {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes().map_err(|e| {
+        eprintln!("Stored TOTP secret is unreadable: {:?}", e);
+        ApiError::Internal
+    })?;
+
+    TOTP::new(Algorithm::SHA1, DIGITS, 0, STEP_SECONDS, bytes, Some(issuer()), account.to_string())
+        .map_err(|e| {
+            eprintln!("Error building TOTP: {:?}", e);
+            ApiError::Internal
+        })
+}
+
+/// A fresh 160-bit shared secret, base32 encoded as authenticator apps expect
+pub fn generate_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+/// `otpauth://` URI to render as a QR code during enrollment
+pub fn provisioning_uri(secret: &str, account: &str) -> Result<String, ApiError> {
+    Ok(build(secret, account)?.get_url())
+}
+
+/// Check `code` against the secret and return the time step it matched.
+/// Steps at or before `last_step` are refused so a code can only be used once.
+pub fn verify_code(
+    secret: &str,
+    code: &str,
+    last_step: Option<i64>,
+) -> Result<Option<i64>, ApiError> {
+    let code = code.trim();
+    if code.len() != DIGITS || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    let totp = build(secret, "")?;
+    let current = chrono::Utc::now().timestamp() / STEP_SECONDS as i64;
+
+    let matched = (-SKEW_STEPS..=SKEW_STEPS)
+        .map(|offset| current + offset)
+        .filter(|step| last_step.map_or(true, |last| *step > last))
+        .find(|step| totp.generate(*step as u64 * STEP_SECONDS) == code);
+
+    Ok(matched)
+}
+
+/// Number of recovery codes handed out per enrollment
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// A one-off recovery code such as `3f9a1-c07be`
+pub fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code = hex::encode(bytes);
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+/// Recovery codes are matched case-insensitively and with or without the dash
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase().replace('-', "")
+}
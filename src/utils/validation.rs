@@ -0,0 +1,142 @@
+use crate::errors::ApiError;
+use serde_json::{Map, Value};
+use std::env;
+
+/// Field name -> list of problems, rendered as the `details` of a validation error
+#[derive(Debug, Default)]
+pub struct FieldErrors(Map<String, Value>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        let entry = self
+            .0
+            .entry(field.to_string())
+            .or_insert_with(|| Value::Array(vec![]));
+        if let Value::Array(messages) = entry {
+            messages.push(Value::String(message.into()));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_details(self) -> Value {
+        Value::Object(self.0)
+    }
+
+    /// `Ok` when nothing was reported, otherwise a 422 carrying every problem
+    pub fn into_result(self, message: &str) -> Result<(), ApiError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::validation(message, self.into_details()))
+        }
+    }
+}
+
+/// Trimmed value, with blank strings treated as absent
+pub fn clean(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// PATCH semantics for optional text: absent keeps the old value, an empty
+/// string clears it
+pub fn merge(new: Option<String>, old: Option<String>) -> Option<String> {
+    match new {
+        Some(value) => clean(Some(value)),
+        None => old,
+    }
+}
+
+/// Emails are stored trimmed and lower-cased so lookups are case-insensitive
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Pragmatic syntax check: `local@domain.tld`, no whitespace, RFC 5321 lengths
+pub fn is_valid_email(email: &str) -> bool {
+    if email.len() > 254 || email.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return false;
+    };
+
+    !local.is_empty()
+        && local.len() <= 64
+        && !local.contains('@')
+        && domain.contains('.')
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'))
+}
+
+/// Absolute `http`/`https` URL with a host, short enough for a VARCHAR(255) column
+pub fn is_valid_url(url: &str) -> bool {
+    url.len() <= 255
+        && reqwest::Url::parse(url)
+            .is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some())
+}
+
+fn env_flag(key: &str, default: bool) -> bool {
+    env::var(key)
+        .map(|v| matches!(v.as_str(), "true" | "1"))
+        .unwrap_or(default)
+}
+
+fn env_usize(key: &str, default: usize) -> usize {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Password strength rules, configured through PASSWORD_* environment variables
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Self {
+        PasswordPolicy {
+            min_length: env_usize("PASSWORD_MIN_LENGTH", 8),
+            max_length: env_usize("PASSWORD_MAX_LENGTH", 128),
+            require_lowercase: env_flag("PASSWORD_REQUIRE_LOWERCASE", true),
+            require_uppercase: env_flag("PASSWORD_REQUIRE_UPPERCASE", true),
+            require_digit: env_flag("PASSWORD_REQUIRE_DIGIT", true),
+            require_symbol: env_flag("PASSWORD_REQUIRE_SYMBOL", false),
+        }
+    }
+
+    /// Every rule the password breaks, empty when it is acceptable
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let mut problems = vec![];
+        let length = password.chars().count();
+
+        if length < self.min_length {
+            problems.push(format!("must be at least {} characters", self.min_length));
+        }
+        if length > self.max_length {
+            problems.push(format!("must be at most {} characters", self.max_length));
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            problems.push("must contain a lowercase letter".to_string());
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            problems.push("must contain an uppercase letter".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            problems.push("must contain a digit".to_string());
+        }
+        if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+            problems.push("must contain a symbol".to_string());
+        }
+
+        problems
+    }
+}